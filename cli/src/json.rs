@@ -1,13 +1,109 @@
+use image::imageops::FilterType;
 use image::ImageFormat;
 use serde::{de, Deserialize, Deserializer};
-use swizzler::session::{GenericTarget, RegexMatcher};
+use swizzler::errors::ErrorKind;
+use swizzler::session::{
+    FileMatch, GenericTarget, GlobMatcher, GroupingScope, MatchPolicy, MissingInputPolicy,
+    RegexMatcher, SuffixMatcher, SymlinkPolicy, TargetInput,
+};
+use swizzler::{BitDepth, ResizePolicy, Transform};
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MatcherType {
+    #[default]
+    Regex,
+    Glob,
+    Suffix,
+}
+
+/// Reader used to group files into assets.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReaderType {
+    /// Extracts the base name of assets from file names, using `base`.
+    #[default]
+    Filename,
+    /// Uses the folder of the files as base name, or extracts it from their
+    /// relative path using `base`.
+    Folder,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Patterns {
+    One(String),
+    Many(Vec<String>),
+}
 
 #[derive(Deserialize)]
-#[serde(remote = "RegexMatcher")]
-struct RegexMatcherDef {
+struct MatcherDef {
     id: String,
-    #[serde(deserialize_with = "de_regexp_from_str")]
-    matcher: regex::Regex,
+
+    #[serde(default, rename = "type")]
+    kind: MatcherType,
+
+    matcher: Patterns,
+
+    #[serde(default)]
+    ignore_case: bool,
+
+    separator: Option<char>,
+}
+
+impl MatcherDef {
+    /// Creates the matcher selected by the `type` field.
+    fn into_matcher(self) -> Result<Box<dyn FileMatch<Identifier = String>>, String> {
+        let pattern = match &self.matcher {
+            Patterns::One(pattern) => Some(pattern.as_str()),
+            Patterns::Many(_) => None,
+        };
+        let single = || pattern.ok_or(format!("matcher '{}' expects a single pattern", self.id));
+        match self.kind {
+            MatcherType::Regex => {
+                let matcher = regex::RegexBuilder::new(single()?)
+                    .case_insensitive(self.ignore_case)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                Ok(Box::new(RegexMatcher::new(self.id.clone(), matcher)))
+            }
+            MatcherType::Glob => {
+                let matcher = GlobMatcher::with_case(self.id.clone(), single()?, !self.ignore_case)
+                    .map_err(|e| e.to_string())?;
+                Ok(Box::new(matcher))
+            }
+            MatcherType::Suffix => {
+                let mut matcher = SuffixMatcher::new(self.id.clone(), "");
+                matcher.tokens = match self.matcher {
+                    Patterns::One(token) => vec![token],
+                    Patterns::Many(tokens) => tokens,
+                };
+                matcher.ignore_case = self.ignore_case;
+                if let Some(separator) = self.separator {
+                    matcher.separator = separator;
+                }
+                Ok(Box::new(matcher))
+            }
+        }
+    }
+}
+
+/// Options used to walk the input folder.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct WalkDef {
+    pub include: Vec<String>,
+
+    pub exclude: Vec<String>,
+
+    pub extensions: Vec<String>,
+
+    pub max_depth: Option<usize>,
+
+    pub skip_hidden: bool,
+
+    #[serde(deserialize_with = "de_symlink_policy_from_str")]
+    pub symlinks: SymlinkPolicy,
 }
 
 #[derive(Deserialize)]
@@ -18,28 +114,53 @@ struct GenericTargetDef {
     #[serde(deserialize_with = "de_image_format_from_str")]
     output_format: image::ImageFormat,
 
-    inputs: Vec<Option<(String, u8)>>,
+    #[serde(default, deserialize_with = "de_bit_depth_from_str")]
+    bit_depth: BitDepth,
+
+    #[serde(default, deserialize_with = "de_resize_policy_from_str")]
+    resize: ResizePolicy,
+
+    #[serde(
+        default = "default_resize_filter",
+        deserialize_with = "de_filter_from_str"
+    )]
+    resize_filter: FilterType,
+
+    #[serde(deserialize_with = "de_vec_input")]
+    inputs: Vec<Option<TargetInput<String>>>,
 }
 
 #[derive(Deserialize)]
 pub struct Config {
-    #[serde(deserialize_with = "de_regexp_from_str")]
-    pub base: regex::Regex,
+    #[serde(default)]
+    pub reader: ReaderType,
+
+    #[serde(default, deserialize_with = "de_opt_regexp_from_str")]
+    pub base: Option<regex::Regex>,
+
+    #[serde(default, deserialize_with = "de_vec_matcher")]
+    pub matchers: Vec<Box<dyn FileMatch<Identifier = String>>>,
+
+    #[serde(default, deserialize_with = "de_match_policy_from_str")]
+    pub match_policy: MatchPolicy,
+
+    #[serde(default, deserialize_with = "de_grouping")]
+    pub grouping: GroupingScope,
 
-    #[serde(deserialize_with = "de_vec_matcher")]
-    pub matchers: Vec<Box<RegexMatcher>>,
+    #[serde(default)]
+    pub walk: WalkDef,
 
     #[serde(deserialize_with = "de_vec_target")]
     pub targets: Vec<GenericTarget>,
 }
 
 /// Deserializes a string from a JSON input into a Regex struct.
-fn de_regexp_from_str<'de, D>(deserializer: D) -> Result<regex::Regex, D::Error>
+fn de_opt_regexp_from_str<'de, D>(deserializer: D) -> Result<Option<regex::Regex>, D::Error>
 where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    regex::Regex::new(&s).map_err(de::Error::custom)
+    regex::Regex::new(&s).map(Some).map_err(de::Error::custom)
 }
 
 /// Deserializes a string from a JSON input into an image::ImageFormat.
@@ -52,16 +173,186 @@ where
     parse_image_format(&s).map_err(D::Error::custom)
 }
 
-/// Deserializes a JSON array into a Vec<Box<RegexMatcher>>.
-fn de_vec_matcher<'de, D>(deserializer: D) -> Result<Vec<Box<RegexMatcher>>, D::Error>
+/// Deserializes a string from a JSON input into a BitDepth.
+fn de_bit_depth_from_str<'de, D>(deserializer: D) -> Result<BitDepth, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?.to_lowercase();
+    parse_bit_depth(&s).map_err(D::Error::custom)
+}
+
+/// Deserializes a string from a JSON input into a ResizePolicy.
+fn de_resize_policy_from_str<'de, D>(deserializer: D) -> Result<ResizePolicy, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?.to_lowercase();
+    parse_resize_policy(&s).map_err(D::Error::custom)
+}
+
+/// Deserializes a string from a JSON input into a FilterType.
+fn de_filter_from_str<'de, D>(deserializer: D) -> Result<FilterType, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?.to_lowercase();
+    parse_filter(&s).map_err(D::Error::custom)
+}
+
+/// Deserializes a string from a JSON input into a MatchPolicy.
+fn de_match_policy_from_str<'de, D>(deserializer: D) -> Result<MatchPolicy, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?.to_lowercase();
+    match s.as_str() {
+        "first" => Ok(MatchPolicy::First),
+        "exclusive" => Ok(MatchPolicy::Exclusive),
+        "all" => Ok(MatchPolicy::All),
+        _ => Err(D::Error::custom(format!(
+            "unsupported match policy '{}'",
+            s
+        ))),
+    }
+}
+
+/// Deserializes a string from a JSON input into a SymlinkPolicy.
+fn de_symlink_policy_from_str<'de, D>(deserializer: D) -> Result<SymlinkPolicy, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+    let s = String::deserialize(deserializer)?.to_lowercase();
+    match s.as_str() {
+        "follow" => Ok(SymlinkPolicy::Follow),
+        "skip" => Ok(SymlinkPolicy::Skip),
+        _ => Err(D::Error::custom(format!(
+            "unsupported symlinks policy '{}'",
+            s
+        ))),
+    }
+}
+
+/// Deserializes either `"directory"`, `"tree"` or a depth into a
+/// GroupingScope.
+fn de_grouping<'de, D>(deserializer: D) -> Result<GroupingScope, D::Error>
 where
     D: Deserializer<'de>,
 {
     #[derive(Deserialize)]
-    struct Wrapper(#[serde(with = "RegexMatcherDef")] RegexMatcher);
+    #[serde(untagged)]
+    enum GroupingDef {
+        Depth(usize),
+        Name(String),
+    }
 
-    let v = Vec::deserialize(deserializer)?;
-    Ok(v.into_iter().map(|Wrapper(a)| Box::new(a)).collect())
+    use serde::de::Error;
+    match GroupingDef::deserialize(deserializer)? {
+        GroupingDef::Depth(depth) => Ok(GroupingScope::Depth(depth)),
+        GroupingDef::Name(name) => match name.to_lowercase().as_str() {
+            "directory" => Ok(GroupingScope::Directory),
+            "tree" => Ok(GroupingScope::Tree),
+            _ => Err(D::Error::custom(format!("unsupported grouping '{}'", name))),
+        },
+    }
+}
+
+fn default_resize_filter() -> FilterType {
+    FilterType::Triangle
+}
+
+/// Deserializes a JSON array into a list of matchers.
+///
+/// The `type` field of each matcher selects its implementation: `regex`, the
+/// default, `glob` or `suffix`. Suffix matchers accept a list of tokens.
+fn de_vec_matcher<'de, D>(
+    deserializer: D,
+) -> Result<Vec<Box<dyn FileMatch<Identifier = String>>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let v: Vec<MatcherDef> = Vec::deserialize(deserializer)?;
+    v.into_iter()
+        .map(|m| m.into_matcher().map_err(de::Error::custom))
+        .collect()
+}
+
+/// Deserializes a JSON array into a Vec<Option<TargetInput>>.
+///
+/// Each input is either `null`, a `[id, channel]` pair, a
+/// `[id, channel, [transforms...]]` triplet, a constant, or an object with
+/// `id`, `channel` and optional `transforms` and `fallback` fields. Inputs
+/// with a `fallback` are optional. Constants and fallbacks must be in the
+/// range `[0, 1]`.
+fn de_vec_input<'de, D>(deserializer: D) -> Result<Vec<Option<TargetInput<String>>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum InputDef {
+        Texture(String, u8),
+        TransformedTexture(String, u8, Vec<String>),
+        Constant(f32),
+        Object {
+            id: String,
+            channel: u8,
+            #[serde(default)]
+            transforms: Vec<String>,
+            fallback: Option<f32>,
+        },
+    }
+
+    let parse_transforms = |transforms: Vec<String>| {
+        transforms
+            .iter()
+            .map(|t| t.parse::<Transform>())
+            .collect::<Result<Vec<Transform>, _>>()
+            .map_err(de::Error::custom)
+    };
+
+    let check_constant = |value: f32| {
+        if (0.0..=1.0).contains(&value) {
+            Ok(value)
+        } else {
+            Err(de::Error::custom(ErrorKind::InvalidDescriptorString(
+                format!("constant '{}' isn't in the range [0, 1]", value),
+            )))
+        }
+    };
+
+    let v: Vec<Option<InputDef>> = Vec::deserialize(deserializer)?;
+    v.into_iter()
+        .map(|input| {
+            input
+                .map(|i| match i {
+                    InputDef::Texture(id, channel) => Ok(TargetInput::from((id, channel))),
+                    InputDef::TransformedTexture(id, channel, transforms) => {
+                        let transforms = parse_transforms(transforms)?;
+                        Ok(TargetInput::from((id, channel, transforms)))
+                    }
+                    InputDef::Constant(value) => check_constant(value).map(TargetInput::Constant),
+                    InputDef::Object {
+                        id,
+                        channel,
+                        transforms,
+                        fallback,
+                    } => {
+                        let input = TargetInput::from((id, channel, parse_transforms(transforms)?));
+                        Ok(match fallback {
+                            Some(value) => input.set_fallback(check_constant(value)?),
+                            None => input,
+                        })
+                    }
+                })
+                .transpose()
+        })
+        .collect()
 }
 
 /// Deserializes a JSON array into a Vec<GenericTarget>.
@@ -78,15 +369,88 @@ where
 
 pub fn parse_image_format(input: &str) -> Result<image::ImageFormat, String> {
     match input {
-        "png" => Ok(ImageFormat::PNG),
-        "jpg" | "jpeg" => Ok(ImageFormat::JPEG),
-        "tif" => Ok(ImageFormat::TIFF),
-        "tga" => Ok(ImageFormat::TGA),
-        "hdr" => Ok(ImageFormat::HDR),
-        "bpm" => Ok(ImageFormat::BMP),
-        "webp" => Ok(ImageFormat::WEBP),
-        "ico" => Ok(ImageFormat::ICO),
-        "pnm" => Ok(ImageFormat::PNM),
+        "png" => Ok(ImageFormat::Png),
+        "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+        "tif" => Ok(ImageFormat::Tiff),
+        "tga" => Ok(ImageFormat::Tga),
+        "hdr" => Ok(ImageFormat::Hdr),
+        "exr" => Ok(ImageFormat::OpenExr),
+        "bpm" => Ok(ImageFormat::Bmp),
+        "webp" => Ok(ImageFormat::WebP),
+        "ico" => Ok(ImageFormat::Ico),
+        "pnm" => Ok(ImageFormat::Pnm),
         _ => Err(format!("unsupported format '{}'", input)),
     }
 }
+
+pub fn parse_missing_input_policy(input: &str) -> Result<MissingInputPolicy, String> {
+    match input {
+        "skip" => Ok(MissingInputPolicy::Skip),
+        "fail" => Ok(MissingInputPolicy::Fail),
+        _ => Err(format!("unsupported missing inputs policy '{}'", input)),
+    }
+}
+
+pub fn parse_bit_depth(input: &str) -> Result<BitDepth, String> {
+    match input {
+        "u8" | "8" => Ok(BitDepth::U8),
+        "u16" | "16" => Ok(BitDepth::U16),
+        "f32" | "32" => Ok(BitDepth::F32),
+        _ => Err(format!("unsupported bit depth '{}'", input)),
+    }
+}
+
+pub fn parse_resize_policy(input: &str) -> Result<ResizePolicy, String> {
+    match input {
+        "error" => Ok(ResizePolicy::Error),
+        "largest" => Ok(ResizePolicy::Largest),
+        "smallest" => Ok(ResizePolicy::Smallest),
+        _ => {
+            let mut split = input.splitn(2, 'x');
+            match (
+                split.next().and_then(|w| w.parse::<u32>().ok()),
+                split.next().and_then(|h| h.parse::<u32>().ok()),
+            ) {
+                (Some(width), Some(height)) => Ok(ResizePolicy::Exact(width, height)),
+                _ => Err(format!("unsupported resize policy '{}'", input)),
+            }
+        }
+    }
+}
+
+pub fn parse_filter(input: &str) -> Result<FilterType, String> {
+    match input {
+        "nearest" => Ok(FilterType::Nearest),
+        "triangle" => Ok(FilterType::Triangle),
+        "catmullrom" => Ok(FilterType::CatmullRom),
+        "gaussian" => Ok(FilterType::Gaussian),
+        "lanczos3" => Ok(FilterType::Lanczos3),
+        _ => Err(format!("unsupported filter '{}'", input)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::json::de_vec_input;
+    use swizzler::session::TargetInput;
+
+    fn parse_inputs(json: &str) -> Result<Vec<Option<TargetInput<String>>>, serde_json::Error> {
+        de_vec_input(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn parse_constant_inputs() {
+        assert_eq!(
+            parse_inputs("[0.5, null, 1]").unwrap(),
+            vec![
+                Some(TargetInput::Constant(0.5)),
+                None,
+                Some(TargetInput::Constant(1.0))
+            ]
+        );
+        assert!(parse_inputs("[-3]").is_err());
+        assert!(parse_inputs("[7]").is_err());
+        assert!(parse_inputs(r#"[{ "id": "ao", "channel": 0, "fallback": 2 }]"#).is_err());
+    }
+}
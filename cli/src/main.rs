@@ -1,12 +1,23 @@
-use regex;
 use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use structopt::StructOpt;
 
-use swizzler::session::{resolve_assets_dir, GenericAssetReader, Session};
-use swizzler::{errors::ErrorKind, to_dynamic, ChannelDescriptor};
+use swizzler::session::{
+    resolve_assets_dir_with_options, resolve_manifest, FolderAssetReader, GenericAssetReader,
+    MissingInputPolicy, ResolveOptions, Session, SessionEvent, SessionPlan, SessionReport,
+};
+use swizzler::{
+    default_threads, errors::ErrorKind, to_dynamic_with_options, unswizzle_named, BitDepth,
+    ChannelSource, ResizePolicy, SwizzleOptions,
+};
 
 mod json;
-use json::{parse_image_format, Config};
+mod manifest;
+use json::{
+    parse_bit_depth, parse_filter, parse_image_format, parse_missing_input_policy,
+    parse_resize_policy, Config, ReaderType,
+};
+use manifest::read_manifest;
 
 #[derive(StructOpt)]
 #[structopt(rename_all = "kebab-case")]
@@ -26,6 +37,49 @@ struct ManualCommand {
 
     #[structopt(long = "format", short, parse(try_from_str = parse_image_format))]
     format: Option<image::ImageFormat>,
+
+    #[structopt(long = "depth", short, parse(try_from_str = parse_bit_depth), default_value = "u8")]
+    depth: BitDepth,
+
+    #[structopt(long = "resize", short, parse(try_from_str = parse_resize_policy), default_value = "error")]
+    resize: ResizePolicy,
+
+    #[structopt(long = "filter", parse(try_from_str = parse_filter), default_value = "triangle")]
+    filter: image::imageops::FilterType,
+
+    /// Number of threads used to swizzle the image. Defaults to the number
+    /// of logical cores.
+    #[structopt(long = "threads", short)]
+    num_threads: Option<usize>,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+/// Struct containing the parsed configuration for a `split` StructOpt
+/// command.
+struct SplitCommand {
+    #[structopt(long = "input", short, parse(from_os_str))]
+    input: std::path::PathBuf,
+
+    /// Name of each channel, in order. `none` skips a channel. Defaults to
+    /// `r`, `g`, `b` and `a`.
+    #[structopt(long = "name", short)]
+    names: Vec<String>,
+
+    /// Folder in which channels are saved. Defaults to the input folder.
+    #[structopt(long = "output", short, parse(from_os_str))]
+    output: Option<std::path::PathBuf>,
+
+    /// Prefix of the generated files. Defaults to the input name, without
+    /// its last `_` suffix.
+    #[structopt(long = "base", short)]
+    base: Option<String>,
+
+    #[structopt(long = "format", short, parse(try_from_str = parse_image_format))]
+    format: Option<image::ImageFormat>,
+
+    #[structopt(long = "depth", short, parse(try_from_str = parse_bit_depth), default_value = "u8")]
+    depth: BitDepth,
 }
 
 #[derive(StructOpt)]
@@ -33,8 +87,23 @@ struct ManualCommand {
 /// Struct containing the parsed configuration for a `session` StructOpt
 /// command.
 struct SessionCommand {
-    #[structopt(long = "folder", short, parse(from_os_str))]
-    folder: std::path::PathBuf,
+    #[structopt(
+        long = "folder",
+        short,
+        parse(from_os_str),
+        required_unless = "manifest"
+    )]
+    folder: Option<std::path::PathBuf>,
+
+    /// CSV or JSON file listing the assets to generate, instead of resolving
+    /// them from a folder.
+    #[structopt(
+        long = "manifest",
+        short,
+        parse(from_os_str),
+        conflicts_with = "folder"
+    )]
+    manifest: Option<std::path::PathBuf>,
 
     #[structopt(long = "config", short, parse(from_os_str))]
     config: Option<std::path::PathBuf>,
@@ -42,6 +111,27 @@ struct SessionCommand {
     #[structopt(long = "threads", short)]
     num_threads: Option<usize>,
 
+    /// Prints what the session would generate, without generating anything.
+    #[structopt(long = "dry-run")]
+    dry_run: bool,
+
+    /// Prints the result of the session, or of the dry run, as JSON.
+    #[structopt(long = "json")]
+    json: bool,
+
+    /// Generates every texture, even those that are up to date.
+    #[structopt(long = "force")]
+    force: bool,
+
+    /// Maximum memory, in megabytes, used by decoded images, shared by all threads.
+    #[structopt(long = "cache-size")]
+    cache_size: Option<usize>,
+
+    /// What to do with textures whose required inputs are missing, `skip` or
+    /// `fail`.
+    #[structopt(long = "missing-inputs", parse(try_from_str = parse_missing_input_policy), default_value = "skip")]
+    missing_inputs: MissingInputPolicy,
+
     #[structopt(
         long = "output",
         short,
@@ -54,6 +144,7 @@ struct SessionCommand {
 #[derive(StructOpt)]
 enum Command {
     Manual(ManualCommand),
+    Split(SplitCommand),
     Session(SessionCommand),
 }
 
@@ -84,7 +175,7 @@ impl std::fmt::Display for CLIError {
             CLIError::JSONError(s) => write!(f, "json parsing failed: '{}'", s),
             CLIError::IOError(e) => write!(f, "config file couldn't be loaded: {}", e),
             CLIError::MissingInput => write!(f, "no inputs provided"),
-            _ => write!(f, "{:?}", self),
+            CLIError::SwizzlerError(e) => write!(f, "{}", e),
         }
     }
 }
@@ -122,29 +213,37 @@ macro_rules! log {
 /// into a new image. This allows user to swizzle anything using this CLI.
 fn process_manual(command: &ManualCommand, quiet: bool) -> Result<(), CLIError> {
     let none_regex = regex::Regex::new(r"(?i)none").unwrap();
-    // Converts inputs into channel descriptors, that the Swizzler library
+    // Converts inputs into channel sources, that the Swizzler library
     // can use to generate the image.
-    let descriptors: Vec<Option<ChannelDescriptor>> = (command
+    let descriptors: Vec<Option<ChannelSource>> = (command
         .inputs
         .iter()
-        .map(|s| -> Result<Option<ChannelDescriptor>, ErrorKind> {
-            if none_regex.is_match(&s) {
+        .map(|s| -> Result<Option<ChannelSource>, ErrorKind> {
+            if none_regex.is_match(s) {
                 Ok(None)
             } else {
-                Ok(Some(ChannelDescriptor::from_description(&s)?))
+                Ok(Some(ChannelSource::from_description(s)?))
             }
         })
-        .collect::<Result<Vec<Option<ChannelDescriptor>>, ErrorKind>>())?;
+        .collect::<Result<Vec<Option<ChannelSource>>, ErrorKind>>())?;
 
-    if descriptors.len() == 0 {
+    if descriptors.is_empty() {
         return Err(CLIError::MissingInput);
     }
 
-    let image = to_dynamic(&descriptors)?;
+    let options = SwizzleOptions {
+        bit_depth: command.depth,
+        resize: command.resize,
+        resize_filter: command.filter,
+        threads: command.num_threads.unwrap_or_else(default_threads),
+    };
+    let image = to_dynamic_with_options(&descriptors, &options)?;
     if let Some(format) = command.format {
-        image.save_with_format(&command.output, format)?;
+        image
+            .save_with_format(&command.output, format)
+            .map_err(ErrorKind::from)?;
     } else {
-        image.save(&command.output)?;
+        image.save(&command.output).map_err(ErrorKind::from)?;
     }
 
     log!(quiet, "Done!");
@@ -152,11 +251,256 @@ fn process_manual(command: &ManualCommand, quiet: bool) -> Result<(), CLIError>
     Ok(())
 }
 
+/// Executes a split command.
+///
+/// A split command extracts each channel of an image into its own grayscale
+/// image, e.g `foo_orm.png` into `foo_ao.png`, `foo_roughness.png` and
+/// `foo_metallic.png`.
+fn process_split(command: &SplitCommand, quiet: bool) -> Result<(), CLIError> {
+    let img = image::open(&command.input).map_err(ErrorKind::from)?;
+
+    let names: Vec<Option<&str>> = if command.names.is_empty() {
+        ["r", "g", "b", "a"]
+            .iter()
+            .take(img.color().channel_count() as usize)
+            .map(|name| Some(*name))
+            .collect()
+    } else {
+        command
+            .names
+            .iter()
+            .map(|name| match name.to_lowercase().as_str() {
+                "none" => None,
+                _ => Some(name.as_str()),
+            })
+            .collect()
+    };
+
+    let stem = command
+        .input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let base = match &command.base {
+        Some(base) => base.clone(),
+        None => match stem.rfind('_') {
+            Some(i) if i > 0 => String::from(&stem[..i]),
+            _ => stem,
+        },
+    };
+    let extension = match command.format {
+        Some(format) => match format.extensions_str().first() {
+            Some(extension) => String::from(*extension),
+            None => {
+                return Err(CLIError::SwizzlerError(ErrorKind::Invalid(format!(
+                    "format {:?} has no file extension",
+                    format
+                ))))
+            }
+        },
+        None => command
+            .input
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from("png")),
+    };
+    let folder = match &command.output {
+        Some(folder) => folder.clone(),
+        None => command
+            .input
+            .parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_default(),
+    };
+    std::fs::create_dir_all(&folder).map_err(ErrorKind::from)?;
+
+    for (name, image) in unswizzle_named(&img, &names, command.depth)? {
+        let path = folder.join(format!("{}_{}.{}", base, name, extension));
+        if let Some(format) = command.format {
+            image
+                .save_with_format(&path, format)
+                .map_err(ErrorKind::from)?;
+        } else {
+            image.save(&path).map_err(ErrorKind::from)?;
+        }
+        log!(quiet, format!("{}", path.display()));
+    }
+
+    log!(quiet, "Done!");
+
+    Ok(())
+}
+
+/// Creates a session observer printing each texture as soon as it's written.
+fn progress_observer() -> impl Fn(&SessionEvent) + Send + Sync {
+    let total = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    move |event: &SessionEvent| match event {
+        SessionEvent::RunStarted { nb_jobs, .. } => total.store(*nb_jobs, Ordering::Relaxed),
+        SessionEvent::FileWritten { path, duration } => {
+            let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+            println!(
+                "[{}/{}] {} ({} ms)",
+                count,
+                total.load(Ordering::Relaxed),
+                path.display(),
+                duration.as_millis()
+            );
+        }
+        SessionEvent::FileSkipped { .. } => {
+            done.fetch_add(1, Ordering::Relaxed);
+        }
+        SessionEvent::TargetSkipped {
+            base,
+            filename,
+            missing,
+        } => {
+            let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+            println!(
+                "[{}/{}] skipped '{}' for asset '{}', missing {}",
+                count,
+                total.load(Ordering::Relaxed),
+                filename,
+                base,
+                missing.join(", ")
+            );
+        }
+        SessionEvent::Error { error } => {
+            // Errors with no asset aren't related to a texture.
+            if let Some(asset) = &error.asset {
+                let count = done.fetch_add(1, Ordering::Relaxed) + 1;
+                println!(
+                    "[{}/{}] failed to generate '{}' for asset '{}'",
+                    count,
+                    total.load(Ordering::Relaxed),
+                    error.target.as_deref().unwrap_or_default(),
+                    asset
+                );
+            }
+        }
+        SessionEvent::RunFinished {
+            nb_errors,
+            duration,
+            ..
+        } => println!(
+            "Session finished in {:.2}s ({} errors)",
+            duration.as_secs_f32(),
+            nb_errors
+        ),
+        _ => {}
+    }
+}
+
+/// Prints a session plan as a table, with one row per texture to generate.
+fn print_plan(plan: &SessionPlan) {
+    for asset in &plan.assets {
+        println!("{}", asset.base);
+        for (id, path) in &asset.textures {
+            println!("    {:<16} {}", id, path.display());
+        }
+        for target in &asset.targets {
+            let status = if !target.missing.is_empty() {
+                format!("missing: {}", target.missing.join(", "))
+            } else if target.up_to_date {
+                String::from("up to date")
+            } else if !target.missing_optional.is_empty() {
+                format!("build, fallback: {}", target.missing_optional.join(", "))
+            } else {
+                String::from("build")
+            };
+            println!("    -> {:<40} [{}]", target.output.display(), status);
+        }
+    }
+    println!(
+        "{} assets, {} textures to build",
+        plan.assets.len(),
+        plan.count_outdated()
+    );
+}
+
+fn plan_to_json(plan: &SessionPlan) -> serde_json::Value {
+    let assets: Vec<serde_json::Value> = plan
+        .assets
+        .iter()
+        .map(|asset| {
+            let textures: serde_json::Map<String, serde_json::Value> = asset
+                .textures
+                .iter()
+                .map(|(id, path)| (id.clone(), path.to_string_lossy().into()))
+                .collect();
+            let targets: Vec<serde_json::Value> = asset
+                .targets
+                .iter()
+                .map(|target| {
+                    serde_json::json!({
+                        "output": target.output.to_string_lossy(),
+                        "inputs": target.inputs.iter().map(|p| p.to_string_lossy()).collect::<Vec<_>>(),
+                        "missing": target.missing,
+                        "missing_optional": target.missing_optional,
+                        "up_to_date": target.up_to_date,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "base": asset.base,
+                "textures": textures,
+                "targets": targets,
+            })
+        })
+        .collect();
+    serde_json::json!({ "assets": assets })
+}
+
+fn report_to_json(report: &SessionReport) -> serde_json::Value {
+    let paths = |paths: &[std::path::PathBuf]| -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect()
+    };
+    let errors: Vec<serde_json::Value> = report
+        .errors
+        .iter()
+        .map(|e| {
+            serde_json::json!({
+                "asset": e.asset,
+                "target": e.target,
+                "inputs": paths(&e.inputs),
+                "output": e.output.as_ref().map(|p| p.to_string_lossy()),
+                "message": e.kind.to_string(),
+            })
+        })
+        .collect();
+    let missing: Vec<serde_json::Value> = report
+        .missing
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "asset": m.asset,
+                "target": m.target,
+                "output": m.output.to_string_lossy(),
+                "required": m.required,
+                "optional": m.optional,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "cancelled": report.is_cancelled(),
+        "written": paths(&report.written),
+        "skipped": paths(&report.skipped),
+        "errors": errors,
+        "missing": missing,
+    })
+}
+
 /// Executes a session command.
 ///
 /// Main function starting a session, reading an input folder, and generating
 /// the swizzled images.
 fn process_session(command: &SessionCommand, quiet: bool) -> Result<(), CLIError> {
+    // Keeps stdout parseable when printing JSON.
+    let quiet = quiet || command.json;
+
     let json = match &command.config {
         Some(path) => std::fs::read_to_string(path),
         _ => {
@@ -169,28 +513,87 @@ fn process_session(command: &SessionCommand, quiet: bool) -> Result<(), CLIError
     let mut config: Config = serde_json::from_str(&json)?;
 
     // Creates a session. This will generate all textures, and save them to disk.
-    let session = Session::new()
+    let mut session = Session::new()
         .set_output_folder(command.output.to_path_buf())
         .add_targets(&mut config.targets)
-        .set_max_threads_nb(command.num_threads);
+        .set_max_threads_nb(command.num_threads)
+        .set_max_cache_size(command.cache_size.map(|mb| mb * 1024 * 1024))
+        .set_force_rebuild(command.force)
+        .set_missing_input_policy(command.missing_inputs);
+    if !quiet {
+        session = session.set_observer(progress_observer());
+    }
 
     // The resolver recursively search for related files in folders. Whenever
     // it matches files together, it save them into a specific structure (an Asset),
     // that the Session will use to generate new textures.
-    let mut resolver = GenericAssetReader::new().set_base(config.base);
-    for m in config.matchers {
-        resolver = resolver.add_matcher(m);
+    let options = ResolveOptions {
+        grouping: config.grouping,
+        include: config.walk.include,
+        exclude: config.walk.exclude,
+        extensions: config.walk.extensions,
+        max_depth: config.walk.max_depth,
+        skip_hidden: config.walk.skip_hidden,
+        symlinks: config.walk.symlinks,
+        // Textures generated by previous runs aren't inputs.
+        exclude_dirs: vec![command.output.to_path_buf()],
+    };
+    let (filename_resolver, folder_resolver, manifest_resolver);
+    // Retrieves all assets, generated by the resolver.
+    let assets = match (&command.manifest, &command.folder) {
+        // The manifest lists assets explicitly, no folder is walked.
+        (Some(path), _) => {
+            log!(
+                quiet,
+                format!("Reading assets from manifest '{}'...", path.display())
+            );
+            manifest_resolver = read_manifest(path).map_err(ErrorKind::Invalid)?;
+            resolve_manifest(&manifest_resolver)
+        }
+        (None, Some(folder)) => {
+            log!(
+                quiet,
+                format!("Building assets from folder '{}'...", folder.display())
+            );
+            match config.reader {
+                ReaderType::Filename => {
+                    let base = config.base.ok_or_else(|| {
+                        ErrorKind::Invalid(String::from("the 'filename' reader requires a 'base'"))
+                    })?;
+                    filename_resolver = GenericAssetReader::new()
+                        .set_base(base)
+                        .set_matchers(config.matchers)
+                        .set_match_policy(config.match_policy);
+                    resolve_assets_dir_with_options(folder, &filename_resolver, &options)?
+                }
+                ReaderType::Folder => {
+                    let mut resolver = FolderAssetReader::new()
+                        .set_root(folder.to_path_buf())
+                        .set_matchers(config.matchers)
+                        .set_match_policy(config.match_policy);
+                    if let Some(base) = config.base {
+                        resolver = resolver.set_base(base);
+                    }
+                    folder_resolver = resolver;
+                    resolve_assets_dir_with_options(folder, &folder_resolver, &options)?
+                }
+            }
+        }
+        (None, None) => return Err(CLIError::MissingInput),
+    };
+    for diagnostic in assets.get_diagnostics() {
+        eprintln!("warning: {}", diagnostic);
     }
 
-    // Retrieves all assets, generated by the resolver.
-    log!(
-        quiet,
-        format!(
-            "Building assets from folder '{}'...",
-            command.folder.to_str().unwrap()
-        )
-    );
-    let assets = resolve_assets_dir(&command.folder, &resolver)?;
+    if command.dry_run {
+        let plan = session.plan(&assets);
+        if command.json {
+            println!("{}", serde_json::to_string_pretty(&plan_to_json(&plan))?);
+        } else {
+            print_plan(&plan);
+        }
+        return Ok(());
+    }
 
     // Starts processing all assets, i.e generating the textures and saving
     // them to disk. All errors are reported in a vector.
@@ -198,10 +601,43 @@ fn process_session(command: &SessionCommand, quiet: bool) -> Result<(), CLIError
         quiet,
         format!("Running session on {} assets", assets.count())
     );
-    let errors = session.run(&assets);
-    for e in &errors {
-        eprintln!("error found while creating texture: {:?}", e);
+    let report = session.run(&assets);
+    if command.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report_to_json(&report))?
+        );
+        return Ok(());
+    }
+    for e in &report.errors {
+        eprintln!("error: {}", e);
+    }
+    let incomplete = report.assets_with_missing_inputs();
+    if !incomplete.is_empty() {
+        eprintln!("warning: {} assets with missing maps:", incomplete.len());
+        for asset in incomplete {
+            let missing: Vec<String> = report
+                .missing
+                .iter()
+                .filter(|m| m.asset == asset)
+                .flat_map(|m| m.required.iter().chain(&m.optional))
+                .fold(Vec::new(), |mut ids, id| {
+                    if !ids.contains(id) {
+                        ids.push(id.clone());
+                    }
+                    ids
+                });
+            eprintln!("    {:<16} {}", asset, missing.join(", "));
+        }
     }
+    log!(
+        quiet,
+        format!(
+            "{} textures rebuilt, {} up to date",
+            report.written.len(),
+            report.skipped.len()
+        )
+    );
 
     log!(quiet, "Done!");
     Ok(())
@@ -211,8 +647,9 @@ fn main() {
     let args = Opt::from_args();
 
     let run = match &args.cmd {
-        Command::Manual(manual) => process_manual(&manual, args.quiet),
-        Command::Session(session) => process_session(&session, args.quiet),
+        Command::Manual(manual) => process_manual(manual, args.quiet),
+        Command::Split(split) => process_split(split, args.quiet),
+        Command::Session(session) => process_session(session, args.quiet),
     };
 
     if let Err(e) = run {
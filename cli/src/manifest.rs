@@ -0,0 +1,158 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use swizzler::session::{ManifestAsset, ManifestAssetReader};
+
+#[derive(Deserialize)]
+struct AssetDef {
+    name: String,
+    textures: BTreeMap<String, PathBuf>,
+    folder: Option<PathBuf>,
+}
+
+/// Reads the list of assets to generate from a manifest file.
+///
+/// Files with a `.csv` extension are read as CSV, others as JSON. Relative
+/// paths of the manifest are relative to the folder containing it.
+pub fn read_manifest(path: &Path) -> Result<ManifestAssetReader, String> {
+    let input = std::fs::read_to_string(path)
+        .map_err(|e| format!("manifest '{}' couldn't be loaded: {}", path.display(), e))?;
+    let is_csv = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+    let assets = if is_csv {
+        parse_csv_manifest(&input)?
+    } else {
+        parse_json_manifest(&input)?
+    };
+    let root = path.parent().unwrap_or_else(|| Path::new("")).to_path_buf();
+    Ok(ManifestAssetReader::new(root).set_assets(assets))
+}
+
+/// Parses a JSON array of assets, e.g:
+///
+/// ```json
+/// [
+///     {
+///         "name": "brick",
+///         "textures": { "albedo": "brick_col.png", "ao": "brick_occ.png" },
+///         "folder": "materials/brick"
+///     }
+/// ]
+/// ```
+fn parse_json_manifest(input: &str) -> Result<Vec<ManifestAsset>, String> {
+    let assets: Vec<AssetDef> = serde_json::from_str(input).map_err(|e| e.to_string())?;
+    Ok(assets
+        .into_iter()
+        .map(|def| ManifestAsset {
+            base: def.name,
+            textures: def.textures.into_iter().collect(),
+            folder: def.folder,
+        })
+        .collect())
+}
+
+/// Parses CSV rows of `asset,id,path[,folder]`, one row per texture. The
+/// first row is skipped when it's a header, as well as empty lines and lines
+/// starting with `#`.
+fn parse_csv_manifest(input: &str) -> Result<Vec<ManifestAsset>, String> {
+    let mut assets = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_csv_line(line).map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if assets.is_empty() && fields.len() > 1 && fields[0] == "asset" && fields[1] == "id" {
+            continue;
+        }
+        let asset = match &fields[..] {
+            [asset, id, path] | [asset, id, path, _] => {
+                ManifestAsset::new(asset.clone()).add_texture(id.clone(), PathBuf::from(path))
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expected 'asset,id,path[,folder]'",
+                    index + 1
+                ))
+            }
+        };
+        // Rows of the same asset are merged by the reader.
+        assets.push(match fields.get(3) {
+            Some(folder) if !folder.is_empty() => asset.set_folder(PathBuf::from(folder)),
+            _ => asset,
+        });
+    }
+    Ok(assets)
+}
+
+/// Splits a CSV line into its fields. Fields can be quoted to contain commas,
+/// with `""` escaping a quote.
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(String::from("unterminated quote"));
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::manifest::{parse_csv_manifest, split_csv_line};
+    use std::path::PathBuf;
+    use swizzler::session::ManifestAsset;
+
+    #[test]
+    fn split_quoted_fields() {
+        assert_eq!(
+            split_csv_line(r#"brick, "albedo, base", "brick ""old"".png""#).unwrap(),
+            vec!["brick", "albedo, base", r#"brick "old".png"#]
+        );
+        assert_eq!(split_csv_line("a,,c").unwrap(), vec!["a", "", "c"]);
+        assert!(split_csv_line(r#"brick,albedo,"brick.png"#).is_err());
+    }
+
+    #[test]
+    fn parse_csv() {
+        let input = "asset,id,path,folder\r\n\
+                     # comment\r\n\
+                     brick,albedo,\"brick, old.png\",materials/brick\r\n\
+                     \r\n\
+                     brick,ao,brick_ao.png\r\n";
+        assert_eq!(
+            parse_csv_manifest(input).unwrap(),
+            vec![
+                ManifestAsset::new(String::from("brick"))
+                    .add_texture(String::from("albedo"), PathBuf::from("brick, old.png"))
+                    .set_folder(PathBuf::from("materials/brick")),
+                ManifestAsset::new(String::from("brick"))
+                    .add_texture(String::from("ao"), PathBuf::from("brick_ao.png")),
+            ]
+        );
+
+        let error = parse_csv_manifest("brick,albedo,brick.png\nbrick,ao,\"brick_ao.png\n");
+        assert_eq!(error, Err(String::from("line 2: unterminated quote")));
+        assert!(parse_csv_manifest("brick,albedo\n").is_err());
+    }
+}
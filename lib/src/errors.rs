@@ -7,10 +7,27 @@ pub enum ErrorKind {
     Image(image::ImageError),
     IOError(std::io::Error),
     InvalidDescriptorString(String),
+    InvalidTransform(String),
     EmptyDescriptor,
     NoInputs,
-    InvalidSize,
-    Invalid,
+    UnsupportedLayout(String),
+    /// Sources don't share the same dimensions.
+    InvalidSize {
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    /// Images are made of one to four channels, but the given number of
+    /// sources was used.
+    InvalidChannelCount(usize),
+    /// A source reads a channel its image doesn't have.
+    InvalidChannel {
+        channel: u8,
+        channels: u8,
+    },
+    /// Required inputs of a target can't be found in the asset.
+    MissingInputs(Vec<String>),
+    /// Invalid parameter, with the reason.
+    Invalid(String),
 }
 
 impl std::error::Error for ErrorKind {
@@ -26,11 +43,32 @@ impl std::fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match &self {
             ErrorKind::InvalidDescriptorString(s) => write!(f, "invalid descriptor string '{}'", s),
+            ErrorKind::InvalidTransform(s) => write!(f, "invalid transform '{}'", s),
             ErrorKind::EmptyDescriptor => {
-                write!(f, "luma image can't be created without any descriptor")
+                write!(f, "image can't be created without any descriptor")
             }
+            ErrorKind::Image(e) => write!(f, "image error: {}", e),
             ErrorKind::IOError(e) => write!(f, "io error: {}", e),
-            _ => write!(f, "{:?}", self),
+            ErrorKind::NoInputs => write!(f, "no image to read the dimensions from"),
+            ErrorKind::UnsupportedLayout(s) => write!(f, "unsupported layout: {}", s),
+            ErrorKind::InvalidSize { expected, actual } => write!(
+                f,
+                "sources have different sizes, expected {}x{} but got {}x{}",
+                expected.0, expected.1, actual.0, actual.1
+            ),
+            ErrorKind::InvalidChannelCount(n) => {
+                write!(f, "expected between 1 and 4 sources, got {}", n)
+            }
+            ErrorKind::InvalidChannel { channel, channels } => write!(
+                f,
+                "can't read channel {} of an image with {} channels",
+                channel, channels
+            ),
+            ErrorKind::MissingInputs(ids) => {
+                let ids: Vec<String> = ids.iter().map(|id| format!("'{}'", id)).collect();
+                write!(f, "missing required inputs {}", ids.join(", "))
+            }
+            ErrorKind::Invalid(reason) => write!(f, "invalid: {}", reason),
         }
     }
 }
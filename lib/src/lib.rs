@@ -1,6 +1,15 @@
 pub mod errors;
 
 mod swizzle;
-pub use swizzle::{to_dynamic, to_luma, to_luma_a, to_rgb, to_rgba, ChannelDescriptor};
+pub use swizzle::{
+    default_threads, resize_sources, swizzle, to_dynamic, to_dynamic_with_depth,
+    to_dynamic_with_options, to_luma, to_luma16, to_luma32f, to_luma_a, to_luma_a16, to_luma_a32f,
+    to_rgb, to_rgb16, to_rgb32f, to_rgba, to_rgba16, to_rgba32f, unswizzle, unswizzle_named,
+    BitDepth, ChannelDescriptor, ChannelSource, Gray16Image, Gray32FImage, GrayAlpha16Image,
+    GrayAlpha32FImage, OutputLayout, ResizePolicy, Rgb16Image, Rgba16Image, SwizzleOptions,
+};
+
+mod transform;
+pub use transform::Transform;
 
 pub mod session;
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use image::DynamicImage;
+
+use crate::errors::ErrorKind;
+
+/// Default memory cap of an [`ImageCache`], in bytes.
+pub const DEFAULT_CACHE_SIZE: usize = 512 * 1024 * 1024;
+
+type Slot = Arc<Mutex<Option<Arc<DynamicImage>>>>;
+
+struct CacheEntry {
+    slot: Slot,
+    size: usize,
+    last_use: u64,
+}
+
+struct CacheData {
+    entries: HashMap<PathBuf, CacheEntry>,
+    size: usize,
+    tick: u64,
+}
+
+/// Cache of decoded images, shared by all the targets being generated.
+///
+/// Images are decoded the first time they are requested, and kept in memory
+/// until they are removed. When the memory used by the decoded images exceeds
+/// the cache capacity, the least recently used images are evicted. An image
+/// larger than the capacity is only kept until another image is decoded.
+///
+/// The cache can be shared between threads. When several threads request the
+/// same image, it's decoded by the first one while the others wait for it.
+pub struct ImageCache {
+    max_size: usize,
+    data: Mutex<CacheData>,
+}
+
+impl Default for ImageCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CACHE_SIZE)
+    }
+}
+
+impl ImageCache {
+    /// Creates an empty cache.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_size` - Maximum number of bytes used by decoded images
+    pub fn new(max_size: usize) -> ImageCache {
+        ImageCache {
+            max_size,
+            data: Mutex::new(CacheData {
+                entries: HashMap::new(),
+                size: 0,
+                tick: 0,
+            }),
+        }
+    }
+
+    /// Returns the decoded image at `path`. The image is decoded only if it
+    /// isn't already in the cache.
+    pub fn get(&self, path: &Path) -> Result<Arc<DynamicImage>, ErrorKind> {
+        let slot = {
+            let mut data = self.data.lock().unwrap();
+            data.tick += 1;
+            let tick = data.tick;
+            let entry = data
+                .entries
+                .entry(path.to_path_buf())
+                .or_insert_with(|| CacheEntry {
+                    slot: Arc::new(Mutex::new(None)),
+                    size: 0,
+                    last_use: tick,
+                });
+            entry.last_use = tick;
+            entry.slot.clone()
+        };
+
+        // Decodes without holding the cache lock, so that other threads can
+        // keep reading other images in the meantime.
+        let mut decoded = slot.lock().unwrap();
+        if let Some(img) = decoded.as_ref() {
+            return Ok(img.clone());
+        }
+        let img = Arc::new(image::open(path)?);
+        *decoded = Some(img.clone());
+        drop(decoded);
+
+        let mut data = self.data.lock().unwrap();
+        let size = img.as_bytes().len();
+        match data.entries.get_mut(path) {
+            Some(entry) if Arc::ptr_eq(&entry.slot, &slot) => entry.size = size,
+            // The entry has been evicted while decoding.
+            _ => return Ok(img),
+        }
+        data.size += size;
+        self.evict(&mut data, path);
+        Ok(img)
+    }
+
+    /// Removes the image at `path` from the cache, if any.
+    pub fn remove(&self, path: &Path) {
+        let mut data = self.data.lock().unwrap();
+        if let Some(entry) = data.entries.remove(path) {
+            data.size -= entry.size;
+        }
+    }
+
+    /// Removes all images from the cache.
+    pub fn clear(&self) {
+        let mut data = self.data.lock().unwrap();
+        data.entries.clear();
+        data.size = 0;
+    }
+
+    /// Returns the number of images currently in the cache.
+    pub fn len(&self) -> usize {
+        self.data.lock().unwrap().entries.len()
+    }
+
+    /// Returns `true` if the cache contains no image, `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of bytes used by the images in the cache.
+    pub fn size(&self) -> usize {
+        self.data.lock().unwrap().size
+    }
+
+    /// Evicts the least recently used images until the cache fits in its
+    /// capacity. The image at `keep`, and images being decoded, are never
+    /// evicted.
+    fn evict(&self, data: &mut CacheData, keep: &Path) {
+        while data.size > self.max_size {
+            let oldest = data
+                .entries
+                .iter()
+                .filter(|(p, e)| e.size > 0 && p.as_path() != keep)
+                .min_by_key(|(_, e)| e.last_use)
+                .map(|(p, _)| p.clone());
+            match oldest {
+                Some(p) => {
+                    let entry = data.entries.remove(&p).unwrap();
+                    data.size -= entry.size;
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::session::ImageCache;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn decode_once() {
+        let cache = ImageCache::default();
+        let path = Path::new("./tests/textures/a_albedo.png");
+        let first = cache.get(path).unwrap();
+        let second = cache.get(path).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.size(), first.as_bytes().len());
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let albedo = Path::new("./tests/textures/a_albedo.png");
+        let ao = Path::new("./tests/textures/a_ao.png");
+
+        // Fits exactly one image.
+        let size = image::open(albedo).unwrap().as_bytes().len();
+        let cache = ImageCache::new(size);
+
+        let first = cache.get(albedo).unwrap();
+        cache.get(ao).unwrap();
+        assert_eq!(cache.len(), 1);
+
+        let second = cache.get(albedo).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+
+        let empty = ImageCache::new(0);
+        empty.get(albedo).unwrap();
+        assert_eq!(empty.len(), 1);
+        empty.clear();
+        assert!(empty.is_empty());
+        assert_eq!(empty.size(), 0);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let paths = [
+            Path::new("./tests/textures/a_albedo.png"),
+            Path::new("./tests/textures/a_ao.png"),
+            Path::new("./tests/textures/b_albedo.jpg"),
+        ];
+        // Fits the largest image only.
+        let max_size = paths
+            .iter()
+            .map(|p| image::open(p).unwrap().as_bytes().len())
+            .max()
+            .unwrap();
+        let cache = ImageCache::new(max_size);
+        let peak = AtomicUsize::new(0);
+
+        crossbeam::scope(|scope| {
+            for thread in 0..4 {
+                let (cache, peak) = (&cache, &peak);
+                scope.spawn(move |_| {
+                    for i in 0..12 {
+                        cache.get(paths[(thread + i) % paths.len()]).unwrap();
+                        peak.fetch_max(cache.size(), Ordering::Relaxed);
+                    }
+                });
+            }
+        })
+        .unwrap();
+        assert!(peak.load(Ordering::Relaxed) <= max_size);
+
+        cache.remove(paths[0]);
+        cache.remove(paths[1]);
+        cache.remove(paths[2]);
+        assert!(cache.is_empty());
+        assert_eq!(cache.size(), 0);
+    }
+}
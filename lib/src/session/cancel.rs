@@ -0,0 +1,35 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Flag used to stop a running [`Session`](super::Session).
+///
+/// The token is cheap to clone, and all clones share the same flag: keep a
+/// clone, give another one to the session, and call [`cancel`] from any
+/// thread to stop it.
+///
+/// The session only checks the token before starting each texture: textures
+/// being generated when the token is cancelled are not interrupted, and are
+/// still written to disk. Stopping a session can thus take as long as
+/// decoding, swizzling and saving its largest texture.
+///
+/// [`cancel`]: CancellationToken::cancel
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Requests the cancellation of the sessions using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if [`cancel`](CancellationToken::cancel) has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::session::{RunStatus, SessionError};
+
+/// Event sent to the observer of a [`Session`](super::Session) while it runs.
+#[derive(Debug)]
+pub enum SessionEvent<'a> {
+    /// The run started. Sent once, before any other event.
+    RunStarted {
+        /// Number of assets in the bundle.
+        nb_assets: usize,
+        /// Number of textures to generate, i.e assets times targets.
+        nb_jobs: usize,
+    },
+    /// The first target of an asset started. Sent before any other event of
+    /// the asset.
+    AssetStarted { base: &'a str },
+    /// A target has been swizzled, but isn't written to disk yet.
+    TargetGenerated {
+        base: &'a str,
+        filename: &'a str,
+        /// Time spent decoding sources and swizzling.
+        duration: Duration,
+    },
+    /// A texture has been written to disk.
+    FileWritten {
+        path: &'a Path,
+        /// Time spent generating, encoding and writing the texture.
+        duration: Duration,
+    },
+    /// A texture is up to date, and hasn't been generated again.
+    FileSkipped { path: &'a Path },
+    /// A texture hasn't been generated, as required inputs are missing. See
+    /// [`MissingInputPolicy`](super::MissingInputPolicy).
+    TargetSkipped {
+        base: &'a str,
+        filename: &'a str,
+        /// Identifiers of the missing inputs.
+        missing: &'a [String],
+    },
+    /// A target of an asset failed.
+    Error { error: &'a SessionError },
+    /// The run is over. Sent once, after every other event.
+    RunFinished {
+        status: RunStatus,
+        nb_errors: usize,
+        duration: Duration,
+    },
+}
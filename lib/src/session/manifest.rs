@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::errors::ErrorKind;
+
+/// Name of the build manifest saved in the output folder of a session.
+pub const MANIFEST_FILENAME: &str = ".swizzler-manifest";
+
+const MANIFEST_HEADER: &str = "swizzler-manifest 1";
+
+/// State of an input file when a texture was generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct InputStamp {
+    path: PathBuf,
+    modified: u128,
+    size: u64,
+}
+
+impl InputStamp {
+    /// Reads the modification time and the size of the file at `path`.
+    pub(crate) fn read(path: &Path) -> Result<InputStamp, ErrorKind> {
+        let metadata = std::fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Ok(InputStamp {
+            path: path.to_path_buf(),
+            modified,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Describes how a texture was generated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ManifestEntry {
+    /// Fingerprint of the target definition.
+    pub(crate) target: String,
+    pub(crate) inputs: Vec<InputStamp>,
+}
+
+/// Build manifest, mapping each generated texture to the inputs and target
+/// definition used to generate it.
+///
+/// The manifest is saved as a text file, with one texture per line. Fields
+/// are separated by tabulations: the texture path relative to the output
+/// folder, the target fingerprint, and then the path, modification time and
+/// size of each input.
+#[derive(Debug, Default)]
+pub(crate) struct Manifest {
+    entries: HashMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Loads the manifest at `path`. A missing manifest, or a manifest with an
+    /// unknown header, is loaded as an empty one, so that every texture is
+    /// generated again. Malformed lines are skipped, so that only the
+    /// textures they describe are generated again.
+    pub(crate) fn load(path: &Path) -> Manifest {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(_) => return Manifest::default(),
+        };
+        let mut lines = content.lines();
+        if lines.next() != Some(MANIFEST_HEADER) {
+            return Manifest::default();
+        }
+        Manifest {
+            entries: lines.filter_map(Manifest::parse_line).collect(),
+        }
+    }
+
+    fn parse_line(line: &str) -> Option<(PathBuf, ManifestEntry)> {
+        let mut fields = line.split('\t');
+        let output = PathBuf::from(fields.next()?);
+        let target = String::from(fields.next()?);
+        let fields: Vec<&str> = fields.collect();
+        let chunks = fields.chunks_exact(3);
+        if !chunks.remainder().is_empty() {
+            return None;
+        }
+        let inputs = chunks
+            .map(|f| {
+                Some(InputStamp {
+                    path: PathBuf::from(f[0]),
+                    modified: f[1].parse().ok()?,
+                    size: f[2].parse().ok()?,
+                })
+            })
+            .collect::<Option<Vec<InputStamp>>>()?;
+        Some((output, ManifestEntry { target, inputs }))
+    }
+
+    /// Saves the manifest at `path`.
+    ///
+    /// Entries whose paths aren't valid UTF-8, or contain tabulations or line
+    /// breaks, are left out. Those textures are generated on every run.
+    pub(crate) fn save(&self, path: &Path) -> Result<(), ErrorKind> {
+        let valid = |p: &Path| match p.to_str() {
+            Some(s) => !s.contains(&['\t', '\n', '\r'][..]),
+            None => false,
+        };
+
+        let mut outputs: Vec<&PathBuf> = self.entries.keys().collect();
+        outputs.sort();
+
+        let mut content = String::from(MANIFEST_HEADER);
+        content.push('\n');
+        for output in outputs {
+            let entry = &self.entries[output];
+            if !valid(output) || !entry.inputs.iter().all(|i| valid(&i.path)) {
+                continue;
+            }
+            content.push_str(&output.to_string_lossy());
+            content.push('\t');
+            content.push_str(&entry.target);
+            for input in &entry.inputs {
+                content.push_str(&format!(
+                    "\t{}\t{}\t{}",
+                    input.path.to_string_lossy(),
+                    input.modified,
+                    input.size
+                ));
+            }
+            content.push('\n');
+        }
+
+        std::fs::File::create(path)?.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the entry of the texture at `output`, relative to the output
+    /// folder.
+    pub(crate) fn get(&self, output: &Path) -> Option<&ManifestEntry> {
+        self.entries.get(output)
+    }
+
+    pub(crate) fn insert(&mut self, output: PathBuf, entry: ManifestEntry) {
+        self.entries.insert(output, entry);
+    }
+
+    pub(crate) fn remove(&mut self, output: &Path) {
+        self.entries.remove(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::session::manifest::{InputStamp, Manifest, ManifestEntry};
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn save_and_load_manifest() {
+        let folder = Path::new("./_tests_output_/manifest");
+        std::fs::create_dir_all(folder).unwrap();
+        let path = folder.join("manifest");
+
+        let entry = ManifestEntry {
+            target: String::from("GenericTarget { name: Some(\"_r.png\") }"),
+            inputs: vec![InputStamp::read(Path::new("./tests/textures/a_albedo.png")).unwrap()],
+        };
+        let mut manifest = Manifest::default();
+        manifest.insert(PathBuf::from("a_r.png"), entry.clone());
+        manifest.insert(PathBuf::from("invalid\tpath.png"), entry.clone());
+        manifest.save(&path).unwrap();
+
+        let loaded = Manifest::load(&path);
+        assert_eq!(loaded.get(Path::new("a_r.png")), Some(&entry));
+        assert_eq!(loaded.get(Path::new("invalid\tpath.png")), None);
+        assert!(Manifest::load(&folder.join("missing")).entries.is_empty());
+    }
+
+    #[test]
+    fn load_corrupt_manifest() {
+        let folder = Path::new("./_tests_output_/manifest_corrupt");
+        std::fs::create_dir_all(folder).unwrap();
+        let path = folder.join("manifest");
+
+        let entry = ManifestEntry {
+            target: String::from("_r.png"),
+            inputs: vec![InputStamp::read(Path::new("./tests/textures/a_albedo.png")).unwrap()],
+        };
+        let mut manifest = Manifest::default();
+        manifest.insert(PathBuf::from("a_r.png"), entry.clone());
+        manifest.insert(PathBuf::from("b_r.png"), entry.clone());
+        manifest.save(&path).unwrap();
+
+        // Truncates the modification time of the last input of `b_r.png`.
+        let content = std::fs::read_to_string(&path).unwrap();
+        let corrupt: Vec<String> = content
+            .lines()
+            .map(|line| {
+                if line.starts_with("b_r.png") {
+                    line[..line.rfind('\t').unwrap() - 1].to_string()
+                } else {
+                    line.to_string()
+                }
+            })
+            .collect();
+        std::fs::write(&path, corrupt.join("\n")).unwrap();
+
+        let loaded = Manifest::load(&path);
+        assert_eq!(loaded.get(Path::new("a_r.png")), Some(&entry));
+        assert_eq!(loaded.get(Path::new("b_r.png")), None);
+    }
+}
@@ -1,27 +1,59 @@
 mod reader;
 pub use reader::{
-    resolve_assets_dir, Asset, AssetBundle, AssetReader, FileMatch, GenericAsset,
-    GenericAssetReader, RegexMatcher,
+    resolve_assets_dir, resolve_assets_dir_with_options, resolve_manifest, Asset, AssetBundle,
+    AssetReader, FileMatch, FolderAssetReader, GenericAsset, GenericAssetReader, GlobMatcher,
+    GroupingScope, ManifestAsset, ManifestAssetReader, MatchPolicy, RegexMatcher,
+    ResolveDiagnostic, ResolveOptions, SuffixMatcher, SymlinkPolicy,
 };
 
+mod cache;
+pub use cache::{ImageCache, DEFAULT_CACHE_SIZE};
+
+mod cancel;
+pub use cancel::CancellationToken;
+
+mod events;
+pub use events::SessionEvent;
+
+mod manifest;
+pub use manifest::MANIFEST_FILENAME;
+use manifest::{InputStamp, Manifest, ManifestEntry};
+
+mod plan;
+pub use plan::{AssetPlan, SessionPlan, TargetPlan};
+
+mod report;
+pub use report::{MissingInputPolicy, MissingInputs, RunStatus, SessionError, SessionReport};
+
 mod writer;
-pub use writer::{GenericTarget, Target};
+pub use writer::{GenericTarget, Target, TargetInput};
 
 use crate::errors::ErrorKind;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Instant;
 
 struct Parameters {
     max_nb_threads: usize,
+    max_cache_size: usize,
+    force_rebuild: bool,
+    missing_inputs: MissingInputPolicy,
 }
 
 impl Parameters {
     fn new() -> Parameters {
         Parameters {
-            max_nb_threads: num_cpus::get(),
+            max_nb_threads: crate::default_threads(),
+            max_cache_size: DEFAULT_CACHE_SIZE,
+            force_rebuild: false,
+            missing_inputs: MissingInputPolicy::default(),
         }
     }
 }
 
+type Observer = Box<dyn Fn(&SessionEvent) + Send + Sync>;
+
 pub struct Session<AssetType: Asset + Sync, T: Target<AssetType> + Sync> {
     output_folder: PathBuf,
 
@@ -29,15 +61,27 @@ pub struct Session<AssetType: Asset + Sync, T: Target<AssetType> + Sync> {
 
     parameters: Parameters,
 
+    observer: Option<Observer>,
+
+    cancellation: Option<CancellationToken>,
+
     _phantom: std::marker::PhantomData<AssetType>,
 }
 
+impl<AssetType: Asset + Sync, T: Target<AssetType> + Sync> Default for Session<AssetType, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<AssetType: Asset + Sync, T: Target<AssetType> + Sync> Session<AssetType, T> {
     pub fn new() -> Session<AssetType, T> {
         Session {
             output_folder: PathBuf::from("./__swizzler_build"),
             targets: Vec::new(),
             parameters: Parameters::new(),
+            observer: None,
+            cancellation: None,
             _phantom: std::marker::PhantomData {},
         }
     }
@@ -47,58 +91,295 @@ impl<AssetType: Asset + Sync, T: Target<AssetType> + Sync> Session<AssetType, T>
         self
     }
 
-    pub fn run(&self, bundle: &AssetBundle<AssetType>) -> Vec<ErrorKind> {
-        if bundle.count() == 0 {
-            return Vec::new();
-        }
+    /// Generates and saves every target of every asset in the bundle.
+    ///
+    /// Each (asset, target) pair is a job pushed in a shared queue. Up to
+    /// `max_threads_nb` workers pop jobs from the queue until it's empty, so
+    /// that no thread stays idle while work remains.
+    ///
+    /// Progress is reported to the observer, if any, using [`SessionEvent`].
+    ///
+    /// A build manifest is kept in the output folder. Textures whose inputs and
+    /// target definition didn't change since they were written are skipped,
+    /// unless the session forces rebuilds.
+    ///
+    /// When the cancellation token is cancelled, workers stop popping jobs
+    /// and the run returns with the [`RunStatus::Cancelled`] status.
+    pub fn run(&self, bundle: &AssetBundle<AssetType>) -> SessionReport {
+        let start = Instant::now();
+        let assets = bundle.get_assets();
+        let nb_jobs = assets.len() * self.targets.len();
+        self.notify(&SessionEvent::RunStarted {
+            nb_assets: assets.len(),
+            nb_jobs,
+        });
 
-        let errors = std::sync::Mutex::new(Vec::new());
+        let errors = Mutex::new(Vec::new());
+        let written = Mutex::new(Vec::new());
+        let skipped = Mutex::new(Vec::new());
+        let missing_inputs = Mutex::new(Vec::new());
 
-        let write_func = |target: &T, asset: &AssetType| -> Result<(), ErrorKind> {
-            let img = target.generate(asset)?;
-            let mut fullpath = self.output_folder.to_path_buf();
-            if let Some(p) = asset.get_folder() {
-                fullpath.push(p.strip_prefix(bundle.get_root()).unwrap());
+        let manifest_path = self.output_folder.join(MANIFEST_FILENAME);
+        let manifest = Mutex::new(Manifest::load(&manifest_path));
+
+        // Jobs are ordered by asset, so that workers share the images of the
+        // assets being processed. A single cache holds the images of every
+        // asset in flight, so that its limit bounds the memory of the whole
+        // run. Images are released once every target of an asset is done.
+        let images = ImageCache::new(self.parameters.max_cache_size);
+        let remaining: Vec<AtomicUsize> = assets
+            .iter()
+            .map(|_| AtomicUsize::new(self.targets.len()))
+            .collect();
+        // The first job of an asset to start sends `AssetStarted`, and the
+        // other jobs of the asset wait for it, so that it's always the first
+        // event of the asset.
+        let started: Vec<Once> = assets.iter().map(|_| Once::new()).collect();
+        let next_job = AtomicUsize::new(0);
+
+        let write_func = |target: &T,
+                          asset: &AssetType,
+                          images: &ImageCache,
+                          filename: &str,
+                          fullpath: &Path|
+         -> Result<(), ErrorKind> {
+            let job_start = Instant::now();
+            let missing = target.get_missing_inputs(asset);
+            let optional = target.get_missing_optional_inputs(asset);
+            if !missing.is_empty() || !optional.is_empty() {
+                missing_inputs.lock().unwrap().push(MissingInputs {
+                    asset: String::from(asset.get_base()),
+                    target: target.get_name(),
+                    output: fullpath.to_path_buf(),
+                    required: missing.clone(),
+                    optional,
+                });
             }
-            fullpath.push(target.get_filename(asset));
+            if !missing.is_empty() {
+                match self.parameters.missing_inputs {
+                    MissingInputPolicy::Skip => {
+                        self.notify(&SessionEvent::TargetSkipped {
+                            base: asset.get_base(),
+                            filename,
+                            missing: &missing,
+                        });
+                        return Ok(());
+                    }
+                    MissingInputPolicy::Fail => return Err(ErrorKind::MissingInputs(missing)),
+                }
+            }
+
+            let relpath = Self::relative_output(bundle, asset, filename);
+            let entry = Self::manifest_entry(target, asset);
+            let up_to_date = !self.parameters.force_rebuild
+                && Self::is_up_to_date(&manifest.lock().unwrap(), &relpath, fullpath, &entry);
+            if up_to_date {
+                self.notify(&SessionEvent::FileSkipped { path: fullpath });
+                skipped.lock().unwrap().push(fullpath.to_path_buf());
+                return Ok(());
+            }
+            manifest.lock().unwrap().remove(&relpath);
+
+            let img = target.generate(asset, images)?;
+            self.notify(&SessionEvent::TargetGenerated {
+                base: asset.get_base(),
+                filename,
+                duration: job_start.elapsed(),
+            });
 
             // Creates directory if doesn't exist.
             std::fs::create_dir_all(fullpath.parent().unwrap())?;
 
-            img.save_with_format(&fullpath, target.get_format())?;
+            img.save_with_format(fullpath, target.get_format())?;
+            self.notify(&SessionEvent::FileWritten {
+                path: fullpath,
+                duration: job_start.elapsed(),
+            });
+            if let Some(entry) = entry {
+                manifest.lock().unwrap().insert(relpath, entry);
+            }
+            written.lock().unwrap().push(fullpath.to_path_buf());
             Ok(())
         };
 
-        let worker_func = |assets: &[AssetType]| {
-            for asset in assets {
-                for target in &self.targets {
-                    if let Err(e) = write_func(target, asset) {
-                        let mut data = errors.lock().unwrap();
-                        data.push(e);
-                    }
+        let worker_func = || loop {
+            if self.is_cancelled() {
+                break;
+            }
+            let job = next_job.fetch_add(1, Ordering::Relaxed);
+            if job >= nb_jobs {
+                break;
+            }
+            let asset_index = job / self.targets.len();
+            let asset = &assets[asset_index];
+            let target_index = job % self.targets.len();
+            let target = &self.targets[target_index];
+            started[asset_index].call_once(|| {
+                self.notify(&SessionEvent::AssetStarted {
+                    base: asset.get_base(),
+                })
+            });
+            let filename = target.get_filename(asset);
+            let fullpath = self
+                .output_folder
+                .join(Self::relative_output(bundle, asset, &filename));
+            if let Err(kind) = write_func(target, asset, &images, &filename, &fullpath) {
+                let error = SessionError {
+                    asset: Some(String::from(asset.get_base())),
+                    target: Some(target.get_name()),
+                    inputs: target.get_inputs(asset),
+                    output: Some(fullpath),
+                    kind,
+                };
+                self.notify(&SessionEvent::Error { error: &error });
+                errors.lock().unwrap().push(error);
+            }
+            if remaining[asset_index].fetch_sub(1, Ordering::AcqRel) == 1 {
+                for (_, path) in asset.get_textures() {
+                    images.remove(path);
                 }
             }
         };
 
-        let assets = bundle.get_assets();
-        let nthreads = std::cmp::min(assets.len() / 2, self.parameters.max_nb_threads);
-        let slice_size: usize = assets.len() / nthreads;
-
+        let nthreads = std::cmp::min(nb_jobs, std::cmp::max(1, self.parameters.max_nb_threads));
         crossbeam::scope(|scope| {
-            for i in 0..nthreads {
-                let start = i * slice_size;
-                let slice = if i < nthreads - 1 {
-                    &assets[start..(start + slice_size)]
-                } else {
-                    &assets[start..]
-                };
-
-                scope.spawn(move |_| (worker_func(slice)));
+            for _ in 0..nthreads {
+                scope.spawn(|_| worker_func());
             }
         })
         .unwrap();
 
-        errors.into_inner().unwrap()
+        // Workers stop popping jobs when cancelled, so the queue isn't
+        // exhausted if the run has been interrupted.
+        let status = if next_job.load(Ordering::Relaxed) < nb_jobs {
+            RunStatus::Cancelled
+        } else {
+            RunStatus::Completed
+        };
+        let mut errors = errors.into_inner().unwrap();
+        if nb_jobs > 0 {
+            let saved = std::fs::create_dir_all(&self.output_folder)
+                .map_err(ErrorKind::from)
+                .and_then(|_| manifest.into_inner().unwrap().save(&manifest_path));
+            if let Err(kind) = saved {
+                let mut error = SessionError::new(kind);
+                error.output = Some(manifest_path);
+                self.notify(&SessionEvent::Error { error: &error });
+                errors.push(error);
+            }
+        }
+        self.notify(&SessionEvent::RunFinished {
+            status,
+            nb_errors: errors.len(),
+            duration: start.elapsed(),
+        });
+        SessionReport {
+            status,
+            written: written.into_inner().unwrap(),
+            skipped: skipped.into_inner().unwrap(),
+            errors,
+            missing: missing_inputs.into_inner().unwrap(),
+        }
+    }
+
+    /// Describes what [`run`](Session::run) would generate for the bundle,
+    /// without decoding nor writing any image.
+    pub fn plan(&self, bundle: &AssetBundle<AssetType>) -> SessionPlan {
+        let manifest = Manifest::load(&self.output_folder.join(MANIFEST_FILENAME));
+        let assets = bundle
+            .get_assets()
+            .iter()
+            .map(|asset| AssetPlan {
+                base: String::from(asset.get_base()),
+                textures: asset
+                    .get_textures()
+                    .into_iter()
+                    .map(|(id, path)| (id, path.to_path_buf()))
+                    .collect(),
+                targets: self
+                    .targets
+                    .iter()
+                    .map(|target| {
+                        let filename = target.get_filename(asset);
+                        let relpath = Self::relative_output(bundle, asset, &filename);
+                        let output = self.output_folder.join(&relpath);
+                        let entry = Self::manifest_entry(target, asset);
+                        TargetPlan {
+                            up_to_date: !self.parameters.force_rebuild
+                                && Self::is_up_to_date(&manifest, &relpath, &output, &entry),
+                            output,
+                            inputs: target.get_inputs(asset),
+                            missing: target.get_missing_inputs(asset),
+                            missing_optional: target.get_missing_optional_inputs(asset),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        SessionPlan { assets }
+    }
+
+    /// Returns the path of a texture, relative to the output folder.
+    fn relative_output(
+        bundle: &AssetBundle<AssetType>,
+        asset: &AssetType,
+        filename: &str,
+    ) -> PathBuf {
+        let mut relpath = PathBuf::new();
+        if let Some(p) = asset.get_folder() {
+            // Folders outside of the root, e.g listed in a manifest, can't
+            // be mirrored in the output folder.
+            if let Ok(p) = p.strip_prefix(bundle.get_root()) {
+                relpath.push(p);
+            }
+        }
+        relpath.push(filename);
+        relpath
+    }
+
+    /// Describes the current state of the target and of its inputs. Returns
+    /// `None` if the target has no fingerprint, or if an input can't be read,
+    /// as the texture can't be up to date.
+    fn manifest_entry(target: &T, asset: &AssetType) -> Option<ManifestEntry> {
+        let fingerprint = target.get_fingerprint();
+        if fingerprint.is_empty() {
+            return None;
+        }
+        target
+            .get_inputs(asset)
+            .iter()
+            .map(|p| InputStamp::read(p))
+            .collect::<Result<Vec<InputStamp>, ErrorKind>>()
+            .ok()
+            .map(|inputs| ManifestEntry {
+                target: fingerprint,
+                inputs,
+            })
+    }
+
+    fn is_up_to_date(
+        manifest: &Manifest,
+        relpath: &Path,
+        fullpath: &Path,
+        entry: &Option<ManifestEntry>,
+    ) -> bool {
+        match entry {
+            Some(entry) => fullpath.exists() && manifest.get(relpath) == Some(entry),
+            None => false,
+        }
+    }
+
+    fn is_cancelled(&self) -> bool {
+        match &self.cancellation {
+            Some(token) => token.is_cancelled(),
+            None => false,
+        }
+    }
+
+    fn notify(&self, event: &SessionEvent) {
+        if let Some(observer) = &self.observer {
+            observer(event);
+        }
     }
 
     pub fn add_target(mut self, target: T) -> Self {
@@ -112,7 +393,79 @@ impl<AssetType: Asset + Sync, T: Target<AssetType> + Sync> Session<AssetType, T>
     }
 
     pub fn set_max_threads_nb(mut self, count: Option<usize>) -> Self {
-        self.parameters.max_nb_threads = count.or(Some(num_cpus::get())).unwrap();
+        self.parameters.max_nb_threads = count.unwrap_or_else(crate::default_threads);
+        self
+    }
+
+    /// Sets a callback receiving the [`SessionEvent`]s of each run.
+    ///
+    /// The callback is called from the worker threads, and should return
+    /// quickly to not slow the session down.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use swizzler::session::{GenericAsset, GenericTarget, Session, SessionEvent};
+    ///
+    /// let session: Session<GenericAsset, GenericTarget> = Session::new()
+    ///     .set_observer(|event: &SessionEvent| {
+    ///         if let SessionEvent::FileWritten { path, duration } = event {
+    ///             println!("{} written in {:?}", path.display(), duration);
+    ///         }
+    ///     });
+    /// ```
+    pub fn set_observer<F: Fn(&SessionEvent) + Send + Sync + 'static>(
+        mut self,
+        observer: F,
+    ) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    /// Sets the token used to cancel runs of this session.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use swizzler::session::{CancellationToken, GenericAsset, GenericTarget, Session};
+    ///
+    /// let token = CancellationToken::new();
+    /// let session: Session<GenericAsset, GenericTarget> =
+    ///     Session::new().set_cancellation_token(token.clone());
+    ///
+    /// // From any thread, stops the session once the textures being
+    /// // generated are written.
+    /// token.cancel();
+    /// ```
+    pub fn set_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Generates every texture when `true`, even those that are up to date
+    /// according to the build manifest.
+    pub fn set_force_rebuild(mut self, force: bool) -> Self {
+        self.parameters.force_rebuild = force;
+        self
+    }
+
+    /// Sets what to do with textures whose required inputs are missing.
+    /// Defaults to [`MissingInputPolicy::Skip`].
+    pub fn set_missing_input_policy(mut self, policy: MissingInputPolicy) -> Self {
+        self.parameters.missing_inputs = policy;
+        self
+    }
+
+    /// Sets the maximum number of bytes used by decoded images. When the limit
+    /// is reached, the least recently used images are evicted and decoded
+    /// again if needed.
+    ///
+    /// The limit is shared by all the assets being processed, whatever the
+    /// number of threads. Images still used by a target being generated stay
+    /// in memory until it's done, even once evicted. Defaults to
+    /// [`DEFAULT_CACHE_SIZE`] when `None`.
+    pub fn set_max_cache_size(mut self, size: Option<usize>) -> Self {
+        self.parameters.max_cache_size = size.unwrap_or(DEFAULT_CACHE_SIZE);
         self
     }
 }
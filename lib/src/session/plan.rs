@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+/// Description of what a [`Session`](super::Session) would generate for a
+/// bundle, created with [`Session::plan`](super::Session::plan).
+#[derive(Clone, Debug, Default)]
+pub struct SessionPlan {
+    pub assets: Vec<AssetPlan>,
+}
+
+/// Plan of a single asset.
+#[derive(Clone, Debug)]
+pub struct AssetPlan {
+    /// Base name of the asset.
+    pub base: String,
+
+    /// Files of the asset, with the identifier each file matched.
+    pub textures: Vec<(String, PathBuf)>,
+
+    /// Textures to generate for this asset, one per target.
+    pub targets: Vec<TargetPlan>,
+}
+
+/// Plan of a single target, for a given asset.
+#[derive(Clone, Debug)]
+pub struct TargetPlan {
+    /// Path the texture would be written to.
+    pub output: PathBuf,
+
+    /// Files read to generate the texture.
+    pub inputs: Vec<PathBuf>,
+
+    /// Identifiers of the required inputs the asset doesn't have. The texture
+    /// can't be generated when there is any.
+    pub missing: Vec<String>,
+
+    /// Identifiers of the optional inputs the asset doesn't have, replaced by
+    /// their fallback value.
+    pub missing_optional: Vec<String>,
+
+    /// `true` if the texture is up to date, and would be skipped.
+    pub up_to_date: bool,
+}
+
+impl SessionPlan {
+    /// Returns the number of textures that would be generated, i.e that aren't
+    /// up to date.
+    pub fn count_outdated(&self) -> usize {
+        self.assets
+            .iter()
+            .flat_map(|a| &a.targets)
+            .filter(|t| !t.up_to_date)
+            .count()
+    }
+}
@@ -1,10 +1,14 @@
 use regex;
+use std::borrow::Cow;
 use std::cmp::Eq;
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Display;
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 
 use crate::errors::ErrorKind;
+use crate::session::MANIFEST_FILENAME;
 
 /// Generalized file name matcher.
 pub trait FileMatch {
@@ -45,6 +49,191 @@ impl<Id: Eq + Hash> FileMatch for RegexMatcher<Id> {
     }
 }
 
+/// Glob-based file matcher.
+///
+/// Use this to match images using a shell-like pattern, e.g `*_ao.{png,tga}`.
+/// The pattern has to match the whole filename, and supports:
+///
+/// * `*` matching any sequence of characters
+/// * `?` matching any single character
+/// * `[abc]`, `[a-z]` and `[!abc]` matching a single character from a set
+/// * `{png,tga}` matching any of the comma-separated alternatives
+#[derive(Clone)]
+pub struct GlobMatcher<Id: Eq + Hash = String> {
+    pub id: Id,
+    glob: String,
+    matcher: regex::Regex,
+}
+
+impl<Id: Eq + Hash> GlobMatcher<Id> {
+    /// Creates a case-sensitive matcher from a glob pattern.
+    pub fn new(id: Id, glob: &str) -> Result<GlobMatcher<Id>, ErrorKind> {
+        GlobMatcher::with_case(id, glob, true)
+    }
+
+    /// Creates a matcher from a glob pattern.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of matched files
+    /// * `glob` - Pattern the filenames are matched against
+    /// * `case_sensitive` - If `false`, letters match regardless of their case
+    pub fn with_case(
+        id: Id,
+        glob: &str,
+        case_sensitive: bool,
+    ) -> Result<GlobMatcher<Id>, ErrorKind> {
+        let matcher = regex::RegexBuilder::new(&glob_to_regex(glob)?)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|e| ErrorKind::Invalid(format!("glob '{}': {}", glob, e)))?;
+        Ok(GlobMatcher {
+            id,
+            glob: String::from(glob),
+            matcher,
+        })
+    }
+
+    /// Returns the pattern this matcher was created from.
+    pub fn get_glob(&self) -> &str {
+        &self.glob
+    }
+}
+
+impl<Id: Eq + Hash> FileMatch for GlobMatcher<Id> {
+    type Identifier = Id;
+
+    fn do_match(&self, filename: &str) -> bool {
+        self.matcher.is_match(filename)
+    }
+
+    fn get_identifier(&self) -> &Self::Identifier {
+        &self.id
+    }
+}
+
+/// Converts a glob pattern into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> Result<String, ErrorKind> {
+    let invalid = |reason: &str| ErrorKind::Invalid(format!("glob '{}': {}", glob, reason));
+
+    let mut regex = String::from("^");
+    let mut alternatives = 0;
+    let mut chars = glob.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '{' => {
+                alternatives += 1;
+                regex.push_str("(?:");
+            }
+            '}' if alternatives > 0 => {
+                alternatives -= 1;
+                regex.push(')');
+            }
+            ',' if alternatives > 0 => regex.push('|'),
+            '[' => {
+                let mut class = String::new();
+                let mut negated = false;
+                loop {
+                    match chars.next() {
+                        Some('!') if class.is_empty() && !negated => negated = true,
+                        Some(']') if !class.is_empty() => break,
+                        // Escapes characters with a meaning in regex sets.
+                        Some(c @ ('\\' | '[' | '^' | '&' | '~')) => {
+                            class.push('\\');
+                            class.push(c);
+                        }
+                        Some(c) => class.push(c),
+                        None => return Err(invalid("unclosed '['")),
+                    }
+                }
+                regex.push('[');
+                if negated {
+                    regex.push('^');
+                }
+                regex.push_str(&class);
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if alternatives > 0 {
+        return Err(invalid("unclosed '{'"));
+    }
+    regex.push('$');
+    Ok(regex)
+}
+
+/// Suffix-based file matcher.
+///
+/// Splits the stem of the filename on a separator, and compares the last token
+/// with the accepted tokens. For instance, `hero_ao.png` has the token `ao`,
+/// but `hero_ao_old.png` and `radiator_albedo.png` don't.
+#[derive(Clone)]
+pub struct SuffixMatcher<Id: Eq + Hash = String> {
+    pub id: Id,
+    /// Accepted tokens.
+    pub tokens: Vec<String>,
+    /// Separator of the tokens in the stem. Defaults to `_`.
+    pub separator: char,
+    /// If `true`, tokens are compared regardless of their case.
+    pub ignore_case: bool,
+}
+
+impl<Id: Eq + Hash> SuffixMatcher<Id> {
+    pub fn new(id: Id, token: &str) -> SuffixMatcher<Id> {
+        SuffixMatcher {
+            id,
+            tokens: vec![String::from(token)],
+            separator: '_',
+            ignore_case: false,
+        }
+    }
+
+    /// Adds another accepted token, e.g `metal` for a `metalness` matcher.
+    pub fn add_token(mut self, token: &str) -> Self {
+        self.tokens.push(String::from(token));
+        self
+    }
+
+    pub fn set_separator(mut self, separator: char) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn set_ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+}
+
+impl<Id: Eq + Hash> FileMatch for SuffixMatcher<Id> {
+    type Identifier = Id;
+
+    fn do_match(&self, filename: &str) -> bool {
+        let stem = match Path::new(filename).file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => stem,
+            None => return false,
+        };
+        let token = match stem.rsplit_once(self.separator) {
+            Some((_, token)) => token,
+            None => stem,
+        };
+        self.tokens.iter().any(|t| {
+            if self.ignore_case {
+                t.eq_ignore_ascii_case(token)
+            } else {
+                t == token
+            }
+        })
+    }
+
+    fn get_identifier(&self) -> &Self::Identifier {
+        &self.id
+    }
+}
+
 /// Generalized asset.
 ///
 /// Assets should contain files that are related one to another. Those files
@@ -59,13 +248,34 @@ pub trait Asset {
     /// Returns the base name of the asset.
     fn get_base(&self) -> &str;
 
-    /// Returns the path to the parent folder, if any.
+    /// Returns the input folder of the asset, usually the deepest folder
+    /// containing all its files, if any. Its path relative to the root of the
+    /// bundle is mirrored in the output folder of the session.
     fn get_folder(&self) -> Option<&Path>;
+
+    /// Returns the files of the asset, with the name of the identifier each
+    /// file matched.
+    ///
+    /// Defaults to no file. Dry runs then list no input for the asset, and
+    /// its decoded images are only released from the cache when evicted.
+    fn get_textures(&self) -> Vec<(String, &Path)> {
+        Vec::new()
+    }
 }
 
+/// Asset created by a [`GenericAssetReader`].
+///
+/// The folder of the asset is the deepest folder containing all its files.
+/// When all files are in the same folder, textures are thus generated next to
+/// them, relative to the output folder. When files are spread across folders,
+/// e.g `hero/albedo/hero_albedo.png` and `hero/masks/hero_ao.png`, textures
+/// are generated in their common folder, i.e `hero`.
 pub struct GenericAsset<'a, Id: Eq + Hash + 'a = String> {
     base: String,
     textures: HashMap<&'a Id, PathBuf>,
+    /// Identifiers of the textures, formatted when they are registered.
+    names: HashMap<&'a Id, String>,
+    folder: Option<PathBuf>,
 }
 
 impl<'a, Id: Eq + Hash> GenericAsset<'a, Id> {
@@ -73,17 +283,40 @@ impl<'a, Id: Eq + Hash> GenericAsset<'a, Id> {
         GenericAsset {
             base,
             textures: HashMap::new(),
+            names: HashMap::new(),
+            folder: None,
         }
     }
 
     pub fn get_texture_path(&self, id: &Id) -> Option<&PathBuf> {
         self.textures.get(id)
     }
+
+    /// Registers the file matched under `id`, and updates the folder of the
+    /// asset.
+    fn insert(&mut self, id: &'a Id, path: PathBuf)
+    where
+        Id: Display,
+    {
+        if let Some(parent) = path.parent() {
+            self.folder = Some(match self.folder.take() {
+                Some(folder) => folder
+                    .components()
+                    .zip(parent.components())
+                    .take_while(|(a, b)| a == b)
+                    .map(|(a, _)| a)
+                    .collect(),
+                None => parent.to_path_buf(),
+            });
+        }
+        self.names.insert(id, id.to_string());
+        self.textures.insert(id, path);
+    }
 }
 
 impl<'a, Identifier: Eq + Hash> Asset for GenericAsset<'a, Identifier> {
     fn empty(&self) -> bool {
-        self.textures.len() == 0
+        self.textures.is_empty()
     }
 
     fn get_base(&self) -> &str {
@@ -91,10 +324,17 @@ impl<'a, Identifier: Eq + Hash> Asset for GenericAsset<'a, Identifier> {
     }
 
     fn get_folder(&self) -> Option<&Path> {
-        match self.textures.values().next() {
-            Some(p) => p.parent(),
-            None => None,
-        }
+        self.folder.as_deref()
+    }
+
+    fn get_textures(&self) -> Vec<(String, &Path)> {
+        let mut textures: Vec<(String, &Path)> = self
+            .textures
+            .iter()
+            .map(|(id, path)| (self.names[id].clone(), path.as_path()))
+            .collect();
+        textures.sort();
+        textures
     }
 }
 
@@ -104,7 +344,130 @@ impl<'a, Identifier: Eq + Hash> Asset for GenericAsset<'a, Identifier> {
 pub trait AssetReader<'a, A: Asset> {
     /// Given a list of files, produce a vector of assets.
     /// Assets should contain files that are related one to another.
+    // Kept as `&Vec` so that existing implementations don't break.
+    #[allow(clippy::ptr_arg)]
     fn resolve(&'a self, files: &Vec<PathBuf>) -> Vec<A>;
+
+    /// Same as [`AssetReader::resolve`], but also reports the problems found
+    /// while matching files into `diagnostics`.
+    ///
+    /// The default implementation doesn't report anything.
+    fn resolve_with_diagnostics(
+        &'a self,
+        files: &Vec<PathBuf>,
+        _diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Vec<A> {
+        self.resolve(files)
+    }
+
+    /// Returns how files must be grouped before being resolved when walking
+    /// an assets directory, overriding [`ResolveOptions::grouping`].
+    ///
+    /// The default implementation returns `None`, using the options.
+    fn get_grouping(&self) -> Option<GroupingScope> {
+        None
+    }
+}
+
+/// Describes what to do with a file matched by several matchers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MatchPolicy {
+    /// The file is used for the first matcher only, in the order matchers
+    /// were added.
+    First,
+    /// The file isn't used at all.
+    Exclusive,
+    /// The file is used for every matcher.
+    #[default]
+    All,
+}
+
+/// Problem found while matching files into assets.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResolveDiagnostic {
+    /// Several files matched the same identifier for an asset. Only the first
+    /// one is used.
+    Conflict {
+        asset: String,
+        id: String,
+        kept: PathBuf,
+        discarded: PathBuf,
+    },
+    /// A file matched several identifiers, and was only used for `used`
+    /// according to the [`MatchPolicy`].
+    Ambiguous {
+        asset: String,
+        file: PathBuf,
+        ids: Vec<String>,
+        used: Vec<String>,
+    },
+    /// A file listed for an asset doesn't exist, and is ignored.
+    NotFound {
+        asset: String,
+        id: String,
+        file: PathBuf,
+    },
+    /// A symbolic link leads to one of the folders containing it, and is
+    /// ignored.
+    SymlinkLoop { link: PathBuf },
+}
+
+impl Display for ResolveDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let quote = |ids: &[String]| -> String {
+            ids.iter()
+                .map(|id| format!("'{}'", id))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        match self {
+            ResolveDiagnostic::SymlinkLoop { link } => write!(
+                f,
+                "{} leads to one of its parent folders, ignoring it",
+                link.display()
+            ),
+            ResolveDiagnostic::NotFound { asset, id, file } => write!(
+                f,
+                "{} listed as '{}' for asset '{}' doesn't exist, ignoring it",
+                file.display(),
+                id,
+                asset
+            ),
+            ResolveDiagnostic::Conflict {
+                asset,
+                id,
+                kept,
+                discarded,
+            } => write!(
+                f,
+                "{} and {} both matched '{}' for asset '{}', using {}",
+                kept.display(),
+                discarded.display(),
+                id,
+                asset,
+                kept.display()
+            ),
+            ResolveDiagnostic::Ambiguous {
+                asset,
+                file,
+                ids,
+                used,
+            } => {
+                write!(
+                    f,
+                    "{} matched {} for asset '{}', ",
+                    file.display(),
+                    quote(ids),
+                    asset
+                )?;
+                if used.is_empty() {
+                    write!(f, "ignoring it")
+                } else {
+                    write!(f, "using it for {}", quote(used))
+                }
+            }
+        }
+    }
 }
 
 /// Generic assets reader.
@@ -114,6 +477,13 @@ pub trait AssetReader<'a, A: Asset> {
 pub struct GenericAssetReader<I: Eq + Hash = String> {
     base: regex::Regex,
     matchers: Vec<Box<dyn FileMatch<Identifier = I>>>,
+    policy: MatchPolicy,
+}
+
+impl<I: Eq + Hash> Default for GenericAssetReader<I> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<I: Eq + Hash> GenericAssetReader<I> {
@@ -121,6 +491,7 @@ impl<I: Eq + Hash> GenericAssetReader<I> {
         GenericAssetReader {
             base: regex::Regex::new(r"(.*)_.*").unwrap(),
             matchers: Vec::new(),
+            policy: MatchPolicy::default(),
         }
     }
 
@@ -161,55 +532,411 @@ impl<I: Eq + Hash> GenericAssetReader<I> {
         self.matchers = matchers;
         self
     }
+
+    /// Sets what to do with files matched by several matchers. Defaults to
+    /// [`MatchPolicy::All`].
+    pub fn set_match_policy(mut self, policy: MatchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl<'a, I: Eq + Hash + Display + 'a> AssetReader<'a, GenericAsset<'a, I>>
+    for GenericAssetReader<I>
+{
+    fn resolve(&'a self, files: &Vec<PathBuf>) -> Vec<GenericAsset<'a, I>> {
+        self.resolve_with_diagnostics(files, &mut Vec::new())
+    }
+
+    /// Groups files into assets, sorted by base name. Files matching no
+    /// matcher are ignored.
+    ///
+    /// When several files match the same identifier for an asset, the first
+    /// one in `files` is used, and the others are reported as conflicts.
+    fn resolve_with_diagnostics(
+        &'a self,
+        files: &Vec<PathBuf>,
+        diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Vec<GenericAsset<'a, I>> {
+        group_files(files, &self.matchers, self.policy, diagnostics, |path| {
+            let filename = path.file_name()?.to_str()?;
+            let base = self.base.captures(filename)?.get(1)?;
+            Some(Cow::Borrowed(base.as_str()))
+        })
+    }
+}
+
+/// Reader creating one asset per folder.
+///
+/// Many asset libraries store each material in its own folder, with files
+/// only named after the texture they contain:
+///
+/// ```sh
+/// $ ls brick_01
+/// albedo.png
+/// roughness.png
+/// ```
+///
+/// By default, the base name of an asset is the name of the folder containing
+/// the files, i.e `brick_01` here. Using [`FolderAssetReader::set_base`], the
+/// base can instead be extracted from the path of the files, relative to the
+/// root set with [`FolderAssetReader::set_root`]. Files of the whole tree are
+/// then resolved together, so that an asset can span several folders, e.g
+/// `brick_01/albedo.png` and `brick_01/textures/roughness.png`.
+pub struct FolderAssetReader<I: Eq + Hash = String> {
+    base: Option<regex::Regex>,
+    root: Option<PathBuf>,
+    matchers: Vec<Box<dyn FileMatch<Identifier = I>>>,
+    policy: MatchPolicy,
+}
+
+impl<I: Eq + Hash> Default for FolderAssetReader<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Eq + Hash> FolderAssetReader<I> {
+    pub fn new() -> FolderAssetReader<I> {
+        FolderAssetReader {
+            base: None,
+            root: None,
+            matchers: Vec::new(),
+            policy: MatchPolicy::default(),
+        }
+    }
+
+    /// Sets the regex used to extract the name of each asset from the path
+    /// of its files, relative to the root. Components of the path are always
+    /// separated by `/`. The first capturing group is used as base name.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use swizzler::session::FolderAssetReader;
+    ///
+    /// // `bricks/brick_01/textures/albedo.png` is part of asset `brick_01`.
+    /// let reader: FolderAssetReader<String> = FolderAssetReader::new()
+    ///     .set_root("./assets".into())
+    ///     .set_base(Regex::new(r"^[^/]+/([^/]+)/").unwrap());
+    /// ```
+    pub fn set_base(mut self, base: regex::Regex) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    /// Sets the folder paths are made relative to before extracting the base
+    /// name. Paths outside of this folder are used as is.
+    pub fn set_root(mut self, root: PathBuf) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Adds a matcher to this reader.
+    ///
+    /// All matchers will be run on all files in order to determine their type.
+    pub fn add_matcher(mut self, matcher: Box<dyn FileMatch<Identifier = I>>) -> Self {
+        self.matchers.push(matcher);
+        self
+    }
+
+    pub fn set_matchers(mut self, matchers: Vec<Box<dyn FileMatch<Identifier = I>>>) -> Self {
+        self.matchers = matchers;
+        self
+    }
+
+    /// Sets what to do with files matched by several matchers. Defaults to
+    /// [`MatchPolicy::All`].
+    pub fn set_match_policy(mut self, policy: MatchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns the base name of the asset `path` is part of, if any.
+    fn _base<'p>(&self, path: &'p Path) -> Option<Cow<'p, str>> {
+        let base = match &self.base {
+            Some(base) => base,
+            None => return path.parent()?.file_name()?.to_str().map(Cow::Borrowed),
+        };
+        let relative = match &self.root {
+            Some(root) => path.strip_prefix(root).unwrap_or(path),
+            None => path,
+        };
+        let relative = relative.to_str()?;
+        let captures = if std::path::MAIN_SEPARATOR == '/' {
+            base.captures(relative)?
+                .get(1)
+                .map(|c| Cow::Borrowed(c.as_str()))
+        } else {
+            let relative = relative.replace(std::path::MAIN_SEPARATOR, "/");
+            let captures = base.captures(&relative)?.get(1);
+            captures.map(|c| Cow::Owned(String::from(c.as_str())))
+        };
+        captures.filter(|c| !c.is_empty())
+    }
 }
 
-impl<'a, I: Eq + Hash + 'a> AssetReader<'a, GenericAsset<'a, I>> for GenericAssetReader<I> {
+impl<'a, I: Eq + Hash + Display + 'a> AssetReader<'a, GenericAsset<'a, I>>
+    for FolderAssetReader<I>
+{
     fn resolve(&'a self, files: &Vec<PathBuf>) -> Vec<GenericAsset<'a, I>> {
+        self.resolve_with_diagnostics(files, &mut Vec::new())
+    }
+
+    /// Groups files into assets by folder, sorted by base name. Files
+    /// matching no matcher are ignored.
+    fn resolve_with_diagnostics(
+        &'a self,
+        files: &Vec<PathBuf>,
+        diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Vec<GenericAsset<'a, I>> {
+        group_files(files, &self.matchers, self.policy, diagnostics, |path| {
+            self._base(path)
+        })
+    }
+
+    /// Files are grouped over the whole tree when the base is extracted from
+    /// their path, as the files of an asset can be in different folders.
+    fn get_grouping(&self) -> Option<GroupingScope> {
+        self.base.as_ref().map(|_| GroupingScope::Tree)
+    }
+}
+
+/// Asset listed in a [`ManifestAssetReader`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManifestAsset<I = String> {
+    /// Name of the asset.
+    pub base: String,
+
+    /// Files of the asset, with the identifier each one is used for. Relative
+    /// paths are relative to the root of the reader.
+    pub textures: Vec<(I, PathBuf)>,
+
+    /// Folder the textures of the asset are generated in, relative to the
+    /// output folder. Defaults to the deepest folder containing all the files
+    /// of the asset, relative to the root of the reader.
+    pub folder: Option<PathBuf>,
+}
+
+impl<I> ManifestAsset<I> {
+    pub fn new(base: String) -> ManifestAsset<I> {
+        ManifestAsset {
+            base,
+            textures: Vec::new(),
+            folder: None,
+        }
+    }
+
+    pub fn add_texture(mut self, id: I, path: PathBuf) -> Self {
+        self.textures.push((id, path));
+        self
+    }
+
+    pub fn set_folder(mut self, folder: PathBuf) -> Self {
+        self.folder = Some(folder);
+        self
+    }
+}
+
+/// Reader creating assets from an explicit list, instead of guessing them
+/// from file names.
+///
+/// Use [`resolve_manifest`] to create an [`AssetBundle`] out of it.
+///
+/// # Example
+///
+/// ```
+/// use swizzler::session::{resolve_manifest, ManifestAsset, ManifestAssetReader};
+///
+/// let reader = ManifestAssetReader::new("./assets".into()).add_asset(
+///     ManifestAsset::new(String::from("brick"))
+///         .add_texture(String::from("albedo"), "textures/brick_col.png".into())
+///         .add_texture(String::from("ao"), "textures/brick_occ.png".into())
+///         .set_folder("materials/brick".into()),
+/// );
+/// let assets = resolve_manifest(&reader);
+/// ```
+pub struct ManifestAssetReader<I = String> {
+    root: PathBuf,
+    assets: Vec<ManifestAsset<I>>,
+}
+
+impl<I> ManifestAssetReader<I> {
+    /// Creates a reader whose relative paths are relative to `root`.
+    pub fn new(root: PathBuf) -> ManifestAssetReader<I> {
+        ManifestAssetReader {
+            root,
+            assets: Vec::new(),
+        }
+    }
+
+    pub fn add_asset(mut self, asset: ManifestAsset<I>) -> Self {
+        self.assets.push(asset);
+        self
+    }
+
+    pub fn set_assets(mut self, assets: Vec<ManifestAsset<I>>) -> Self {
+        self.assets = assets;
+        self
+    }
+
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn get_assets(&self) -> &[ManifestAsset<I>] {
+        &self.assets
+    }
+}
+
+impl<'a, I: Eq + Hash + Display + 'a> AssetReader<'a, GenericAsset<'a, I>>
+    for ManifestAssetReader<I>
+{
+    fn resolve(&'a self, files: &Vec<PathBuf>) -> Vec<GenericAsset<'a, I>> {
+        self.resolve_with_diagnostics(files, &mut Vec::new())
+    }
+
+    /// Creates the listed assets, sorted by base name. Listed files that
+    /// aren't part of `files` are ignored, and reported as not found.
+    ///
+    /// Assets listed several times are merged. When several files are listed
+    /// under the same identifier for an asset, the first one is used, and the
+    /// others are reported as conflicts.
+    fn resolve_with_diagnostics(
+        &'a self,
+        files: &Vec<PathBuf>,
+        diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Vec<GenericAsset<'a, I>> {
+        let files: HashSet<&PathBuf> = files.iter().collect();
         let mut result: Vec<GenericAsset<'a, I>> = Vec::new();
+        let mut indices: HashMap<&str, usize> = HashMap::new();
 
-        // TODO: how would it be possible to use a HashMap<&str, usize> here?
-        // Obviously this would create mut and immut references at the same
-        // time, making the borrow checker un-happy...
+        for entry in &self.assets {
+            let idx = *indices.entry(&entry.base).or_insert_with(|| {
+                result.push(GenericAsset::new(entry.base.clone()));
+                result.len() - 1
+            });
+            let asset = &mut result[idx];
 
-        for path in files {
-            if let Some(filename) = path.file_name().and_then(|x| x.to_str()) {
-                let base = self.base.captures(filename).and_then(|v| v.get(1));
-                if base.is_none() {
+            for (id, path) in &entry.textures {
+                let path = self.root.join(path);
+                if !files.contains(&path) {
+                    diagnostics.push(ResolveDiagnostic::NotFound {
+                        asset: entry.base.clone(),
+                        id: id.to_string(),
+                        file: path,
+                    });
                     continue;
                 }
-
-                let base = base.unwrap().as_str();
-
-                let idx = result
-                    .iter()
-                    .position(|e| e.base == base)
-                    .or_else(|| -> Option<usize> {
-                        let asset = GenericAsset::new(String::from(base));
-                        result.push(asset);
-                        Some(result.len() - 1)
-                    })
-                    .unwrap();
-
-                let asset = result.get_mut(idx).unwrap();
-                if asset.base == base {
-                    for m in &self.matchers {
-                        if m.do_match(filename) {
-                            // TODO: how to move here instead of clone?
-                            asset.textures.insert(&m.get_identifier(), path.clone());
-                        }
-                    }
+                match asset.textures.entry(id) {
+                    Entry::Occupied(e) => diagnostics.push(ResolveDiagnostic::Conflict {
+                        asset: entry.base.clone(),
+                        id: id.to_string(),
+                        kept: e.get().clone(),
+                        discarded: path,
+                    }),
+                    Entry::Vacant(_) => asset.insert(id, path),
                 }
             }
         }
 
+        // Explicit folders win over the folders guessed from the files.
+        for entry in &self.assets {
+            if let Some(folder) = &entry.folder {
+                result[indices[entry.base.as_str()]].folder = Some(self.root.join(folder));
+            }
+        }
+
+        result.sort_by(|a, b| a.base.cmp(&b.base));
         result
     }
 }
 
+/// Groups `files` into assets, sorted by base name, using `base` to extract
+/// the base name of each file.
+///
+/// Matchers are run on file names. When several files match the same
+/// identifier for an asset, the first one in `files` is used, and the others
+/// are reported as conflicts.
+fn group_files<'a, 'p, I, F>(
+    files: &'p [PathBuf],
+    matchers: &'a [Box<dyn FileMatch<Identifier = I>>],
+    policy: MatchPolicy,
+    diagnostics: &mut Vec<ResolveDiagnostic>,
+    base: F,
+) -> Vec<GenericAsset<'a, I>>
+where
+    I: Eq + Hash + Display + 'a,
+    F: Fn(&'p Path) -> Option<Cow<'p, str>>,
+{
+    let mut result: Vec<GenericAsset<'a, I>> = Vec::new();
+    // Index of each asset in `result`, by base name. Bases borrow the paths
+    // when possible, so that only bases of new assets are copied.
+    let mut indices: HashMap<Cow<'p, str>, usize> = HashMap::new();
+
+    for path in files {
+        let filename = match path.file_name().and_then(|x| x.to_str()) {
+            Some(filename) => filename,
+            None => continue,
+        };
+        let base = match base(path) {
+            Some(base) => base,
+            None => continue,
+        };
+
+        let matched: Vec<&'a I> = matchers
+            .iter()
+            .filter(|m| m.do_match(filename))
+            .map(|m| m.get_identifier())
+            .collect();
+        // Files matching nothing would only create empty assets.
+        if matched.is_empty() {
+            continue;
+        }
+
+        let idx = *indices.entry(base).or_insert_with_key(|base| {
+            result.push(GenericAsset::new(base.to_string()));
+            result.len() - 1
+        });
+        let asset = &mut result[idx];
+
+        let used = match policy {
+            MatchPolicy::All => &matched[..],
+            MatchPolicy::First => &matched[..1],
+            MatchPolicy::Exclusive if matched.len() > 1 => &[],
+            MatchPolicy::Exclusive => &matched[..],
+        };
+        if used.len() < matched.len() {
+            diagnostics.push(ResolveDiagnostic::Ambiguous {
+                asset: asset.base.clone(),
+                file: path.clone(),
+                ids: matched.iter().map(|id| id.to_string()).collect(),
+                used: used.iter().map(|id| id.to_string()).collect(),
+            });
+        }
+        for id in used {
+            match asset.textures.entry(id) {
+                Entry::Occupied(entry) => diagnostics.push(ResolveDiagnostic::Conflict {
+                    asset: asset.base.clone(),
+                    id: id.to_string(),
+                    kept: entry.get().clone(),
+                    discarded: path.clone(),
+                }),
+                Entry::Vacant(_) => asset.insert(id, path.clone()),
+            }
+        }
+    }
+
+    result.sort_by(|a, b| a.base.cmp(&b.base));
+    result
+}
+
 /// List of assets resolved relative to a given root folder.
 pub struct AssetBundle<A: Asset> {
     root: PathBuf,
     assets: Vec<A>,
+    diagnostics: Vec<ResolveDiagnostic>,
 }
 
 impl<A: Asset> AssetBundle<A> {
@@ -224,6 +951,81 @@ impl<A: Asset> AssetBundle<A> {
     pub fn count(&self) -> usize {
         self.assets.len()
     }
+
+    /// Returns the problems found while resolving the assets, e.g files
+    /// matching the same identifier for an asset.
+    pub fn get_diagnostics(&self) -> &[ResolveDiagnostic] {
+        &self.diagnostics
+    }
+}
+
+/// Describes which files of a directory tree are grouped together into
+/// assets by [`resolve_assets_dir_with_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GroupingScope {
+    /// Files are grouped with the files of the same directory only.
+    #[default]
+    Directory,
+    /// Files are grouped with all the files of the tree.
+    Tree,
+    /// Files are grouped with the files sharing the same ancestor directory
+    /// at the given depth, the root being at depth `0`. Files of shallower
+    /// directories are grouped per directory.
+    ///
+    /// For instance with a depth of `1`, `hero/albedo/hero_albedo.png` and
+    /// `hero/masks/hero_ao.png` are grouped together, under `hero`.
+    Depth(usize),
+}
+
+/// Describes how symbolic links are handled when walking an assets directory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Links are followed. Links leading to a folder already walked are
+    /// ignored, so that loops don't walk forever.
+    #[default]
+    Follow,
+    /// Links are ignored.
+    Skip,
+}
+
+/// Options used to resolve an assets directory.
+///
+/// Patterns of `include` and `exclude` are globs, supporting the same syntax
+/// as [`GlobMatcher`]. Patterns containing a `/` are matched against the path
+/// of entries relative to the root, e.g `textures/*.png`, others against
+/// their name only, e.g `*.psd` or `thumbnails`.
+///
+/// Folders containing the manifest of a session, i.e previous outputs, are
+/// never walked, nor is the root itself when it's one of them. Folders are
+/// walked in the order of their names, and folders reached several times
+/// through links are only walked once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResolveOptions {
+    /// Which files are grouped together into assets. Readers can override it
+    /// using [`AssetReader::get_grouping`].
+    pub grouping: GroupingScope,
+
+    /// Files must match one of these patterns, if any.
+    pub include: Vec<String>,
+
+    /// Files and folders matching any of these patterns are ignored.
+    pub exclude: Vec<String>,
+
+    /// Files must have one of these extensions, if any. Extensions are
+    /// compared regardless of their case, without the leading `.`.
+    pub extensions: Vec<String>,
+
+    /// Maximum depth of the folders walked, the root being at depth `0`.
+    pub max_depth: Option<usize>,
+
+    /// Ignores files and folders whose name starts with a `.`.
+    pub skip_hidden: bool,
+
+    /// How symbolic links are handled.
+    pub symlinks: SymlinkPolicy,
+
+    /// Folders that are never walked, e.g the output folder of the session.
+    pub exclude_dirs: Vec<PathBuf>,
 }
 
 /// Resolves an assets directory.
@@ -252,33 +1054,386 @@ impl<A: Asset> AssetBundle<A> {
 pub fn resolve_assets_dir<'a, A: Asset, Resolver: AssetReader<'a, A>>(
     dir: &Path,
     resolver: &'a Resolver,
+) -> Result<AssetBundle<A>, ErrorKind> {
+    resolve_assets_dir_with_options(dir, resolver, &ResolveOptions::default())
+}
+
+/// Resolves an assets directory, using the given options.
+///
+/// # Example
+///
+/// ```rust
+/// use swizzler::session::{
+///     resolve_assets_dir_with_options, GenericAssetReader, GroupingScope, ResolveOptions,
+/// };
+///
+/// let resolver: GenericAssetReader<String> = GenericAssetReader::new();
+///
+/// // Groups files of the whole tree, e.g `hero/albedo/hero_albedo.png` and
+/// // `hero/masks/hero_ao.png` are part of the same asset.
+/// let options = ResolveOptions {
+///     grouping: GroupingScope::Tree,
+///     ..ResolveOptions::default()
+/// };
+/// let assets = resolve_assets_dir_with_options(
+///     std::path::Path::new("./"),
+///     &resolver,
+///     &options,
+/// );
+/// ```
+pub fn resolve_assets_dir_with_options<'a, A: Asset, Resolver: AssetReader<'a, A>>(
+    dir: &Path,
+    resolver: &'a Resolver,
+    options: &ResolveOptions,
 ) -> Result<AssetBundle<A>, ErrorKind> {
     let mut bundle = AssetBundle {
         root: dir.to_path_buf(),
         assets: Vec::new(),
+        diagnostics: Vec::new(),
     };
-    resolve_dir_rec(dir, &mut bundle.assets, resolver)?;
+    let mut files = Vec::new();
+    Walker::new(dir, options)?.walk_root(&mut files, &mut bundle.diagnostics)?;
+
+    // Groups files by directory, cut at the grouping depth. Groups are
+    // sorted, so that conflicts are always resolved the same way.
+    let depth = match resolver.get_grouping().unwrap_or(options.grouping) {
+        GroupingScope::Directory => usize::MAX,
+        GroupingScope::Tree => 0,
+        GroupingScope::Depth(depth) => depth,
+    };
+    let mut groups: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+    for file in files {
+        let parent = file.parent().unwrap_or(dir);
+        let relative = parent.strip_prefix(dir).unwrap_or(parent);
+        let group = dir.join(relative.components().take(depth).collect::<PathBuf>());
+        groups.entry(group).or_default().push(file);
+    }
+
+    for (_, mut files) in groups {
+        files.sort();
+        let mut assets = resolver.resolve_with_diagnostics(&files, &mut bundle.diagnostics);
+        bundle.assets.append(&mut assets);
+    }
     bundle.assets.retain(|e| !e.empty());
+    // Stable, so that assets sharing a base stay in the order of their folders.
+    bundle.assets.sort_by(|a, b| a.get_base().cmp(b.get_base()));
     Ok(bundle)
 }
 
-/// Recursive body of `resolve_assets_dir`.
-fn resolve_dir_rec<'a, A: Asset, Resolver: AssetReader<'a, A>>(
-    curr_dir: &Path,
-    out: &mut Vec<A>,
-    resolver: &'a Resolver,
-) -> std::io::Result<()> {
-    let mut files = std::fs::read_dir(curr_dir)?
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, std::io::Error>>()?;
-    for path in &files {
-        if path.is_dir() {
-            resolve_dir_rec(path, out, resolver)?;
+/// Resolves the assets listed by a [`ManifestAssetReader`].
+///
+/// Unlike [`resolve_assets_dir`], no directory is walked: only the listed
+/// files are used, whatever their names. Listed files that don't exist are
+/// reported in the diagnostics of the bundle, and the textures using them
+/// are handled like any other texture with missing inputs. Every listed
+/// asset is part of the bundle, even when none of its files exist.
+///
+/// The root of the bundle is the root of the reader, so that outputs are
+/// generated relative to it.
+pub fn resolve_manifest<'a, I: Eq + Hash + Display + 'a>(
+    reader: &'a ManifestAssetReader<I>,
+) -> AssetBundle<GenericAsset<'a, I>> {
+    let mut files: Vec<PathBuf> = reader
+        .assets
+        .iter()
+        .flat_map(|asset| asset.textures.iter())
+        .map(|(_, path)| reader.root.join(path))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+    files.dedup();
+
+    let mut bundle = AssetBundle {
+        root: reader.root.clone(),
+        assets: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+    bundle.assets = reader.resolve_with_diagnostics(&files, &mut bundle.diagnostics);
+    bundle
+}
+
+/// Lists recursively the files of an assets directory, according to the
+/// [`ResolveOptions`].
+///
+/// Only files are listed, so that resolvers don't take care of discarding
+/// paths pointing to directories.
+struct Walker<'o> {
+    root: &'o Path,
+    options: &'o ResolveOptions,
+    /// Compiled globs, and whether they match relative paths or names.
+    include: Vec<(regex::Regex, bool)>,
+    exclude: Vec<(regex::Regex, bool)>,
+    exclude_dirs: Vec<PathBuf>,
+    /// Canonical paths of the folders walked so far.
+    visited: HashSet<PathBuf>,
+    /// Canonical paths of the folders containing the one being walked.
+    ancestors: Vec<PathBuf>,
+}
+
+impl<'o> Walker<'o> {
+    fn new(root: &'o Path, options: &'o ResolveOptions) -> Result<Walker<'o>, ErrorKind> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|glob| {
+                    let regex = regex::Regex::new(&glob_to_regex(glob)?)
+                        .map_err(|e| ErrorKind::Invalid(format!("glob '{}': {}", glob, e)))?;
+                    Ok((regex, glob.contains('/')))
+                })
+                .collect::<Result<Vec<_>, ErrorKind>>()
+        };
+        let canonical = std::fs::canonicalize(root)?;
+        let mut visited = HashSet::new();
+        visited.insert(canonical.clone());
+        Ok(Walker {
+            root,
+            options,
+            include: compile(&options.include)?,
+            exclude: compile(&options.exclude)?,
+            // Folders that don't exist yet can't be walked anyway.
+            exclude_dirs: options
+                .exclude_dirs
+                .iter()
+                .filter_map(|dir| std::fs::canonicalize(dir).ok())
+                .collect(),
+            visited,
+            ancestors: vec![canonical],
+        })
+    }
+
+    /// Walks the root, unless it's excluded or a previous output.
+    fn walk_root(
+        &mut self,
+        out: &mut Vec<PathBuf>,
+        diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Result<(), ErrorKind> {
+        if self.exclude_dirs.contains(&self.ancestors[0])
+            || self.root.join(MANIFEST_FILENAME).is_file()
+        {
+            return Ok(());
+        }
+        self.walk(self.root, 0, out, diagnostics)
+    }
+
+    fn walk(
+        &mut self,
+        curr_dir: &Path,
+        depth: usize,
+        out: &mut Vec<PathBuf>,
+        diagnostics: &mut Vec<ResolveDiagnostic>,
+    ) -> Result<(), ErrorKind> {
+        let mut entries = std::fs::read_dir(curr_dir)?.collect::<Result<Vec<_>, _>>()?;
+        // Sorted, so that the files found are always matched the same way.
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if self.options.skip_hidden && name.starts_with('.') {
+                continue;
+            }
+            let relative = path.strip_prefix(self.root).unwrap_or(&path);
+            let relative = relative
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/");
+            let matches = |(glob, is_path): &(regex::Regex, bool)| {
+                glob.is_match(if *is_path { &relative } else { &name })
+            };
+            if self.exclude.iter().any(matches) {
+                continue;
+            }
+
+            let is_symlink = entry.file_type()?.is_symlink();
+            if is_symlink && self.options.symlinks == SymlinkPolicy::Skip {
+                continue;
+            }
+            // Follows links. Broken links are ignored.
+            let metadata = match std::fs::metadata(&path) {
+                Ok(metadata) => metadata,
+                Err(_) if is_symlink => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            if metadata.is_dir() {
+                if self.options.max_depth.is_some_and(|max| depth >= max)
+                    || path.join(MANIFEST_FILENAME).is_file()
+                {
+                    continue;
+                }
+                let canonical = std::fs::canonicalize(&path)?;
+                if self.exclude_dirs.contains(&canonical) {
+                    continue;
+                }
+                if self.ancestors.contains(&canonical) {
+                    diagnostics.push(ResolveDiagnostic::SymlinkLoop { link: path });
+                    continue;
+                }
+                // Other folders reached again through links are walked once.
+                if !self.visited.insert(canonical.clone()) {
+                    continue;
+                }
+                self.ancestors.push(canonical);
+                self.walk(&path, depth + 1, out, diagnostics)?;
+                self.ancestors.pop();
+            } else if metadata.is_file() {
+                let extensions = &self.options.extensions;
+                if !extensions.is_empty() {
+                    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+                    if !extensions.iter().any(|e| e.eq_ignore_ascii_case(extension)) {
+                        continue;
+                    }
+                }
+                if !self.include.is_empty() && !self.include.iter().any(matches) {
+                    continue;
+                }
+                out.push(path);
+            }
         }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::session::{
+        Asset, AssetReader, FileMatch, FolderAssetReader, GenericAsset, GenericAssetReader,
+        GlobMatcher, SuffixMatcher,
+    };
+    use std::path::PathBuf;
+
+    #[test]
+    fn asset_textures() {
+        // Identifiers don't need to be displayable, and `get_textures` is
+        // optional.
+        #[derive(PartialEq, Eq, Hash)]
+        struct Channel;
+        struct Minimal;
+        impl Asset for Minimal {
+            fn empty(&self) -> bool {
+                false
+            }
+            fn get_base(&self) -> &str {
+                "minimal"
+            }
+            fn get_folder(&self) -> Option<&std::path::Path> {
+                None
+            }
+        }
+
+        let asset: GenericAsset<Channel> = GenericAsset::new(String::from("a"));
+        assert!(Asset::get_textures(&asset).is_empty());
+        assert!(Minimal.get_textures().is_empty());
+    }
+
+    #[test]
+    fn glob_matcher() {
+        let matcher = GlobMatcher::new("ao", "*_ao.{png,tga}").unwrap();
+        assert!(matcher.do_match("hero_ao.png"));
+        assert!(matcher.do_match("hero_ao.tga"));
+        assert!(!matcher.do_match("hero_ao.jpg"));
+        assert!(!matcher.do_match("radiator_albedo.png"));
+        assert!(!matcher.do_match("hero_AO.png"));
+
+        let matcher = GlobMatcher::with_case("ao", "*_ao.png", false).unwrap();
+        assert!(matcher.do_match("hero_AO.PNG"));
+
+        let matcher = GlobMatcher::new("rough", "hero_?_[!a-c]ough.png").unwrap();
+        assert!(matcher.do_match("hero_1_rough.png"));
+        assert!(!matcher.do_match("hero_1_cough.png"));
+        assert!(!matcher.do_match("hero_12_rough.png"));
+
+        // Regex characters are matched literally.
+        let matcher = GlobMatcher::new("ao", "a+b.png").unwrap();
+        assert!(matcher.do_match("a+b.png"));
+        assert!(!matcher.do_match("aab.png"));
+
+        assert!(GlobMatcher::new("ao", "*_{ao,occlusion.png").is_err());
+        assert!(GlobMatcher::new("ao", "*_[ao.png").is_err());
+    }
+
+    #[test]
+    fn suffix_matcher() {
+        let matcher = SuffixMatcher::new("ao", "ao");
+        assert!(matcher.do_match("hero_ao.png"));
+        assert!(matcher.do_match("ao.png"));
+        assert!(!matcher.do_match("hero_ao_old.png"));
+        assert!(!matcher.do_match("radiator_albedo.png"));
+        assert!(!matcher.do_match("hero_AO.png"));
+
+        let matcher = SuffixMatcher::new("metalness", "metalness")
+            .add_token("metal")
+            .set_separator('-')
+            .set_ignore_case(true);
+        assert!(matcher.do_match("hero-Metal.png"));
+        assert!(matcher.do_match("hero_x-metalness.tga"));
+        assert!(!matcher.do_match("hero_metal.png"));
+    }
+
+    #[test]
+    fn resolve_sorted_by_base() {
+        let reader = GenericAssetReader::new()
+            .add_matcher(Box::new(SuffixMatcher::new("albedo", "albedo")))
+            .add_matcher(Box::new(SuffixMatcher::new("ao", "ao")));
+
+        // Interleaves the files of many assets, with files matching nothing.
+        let count = 5000;
+        let mut files: Vec<PathBuf> = Vec::new();
+        for texture in &["ao", "albedo", "normal"] {
+            for i in (0..count).rev() {
+                files.push(PathBuf::from(format!("./{:05}_{}.png", i, texture)));
+            }
+        }
+        files.push(PathBuf::from("./no-base.png"));
+
+        let assets = reader.resolve(&files);
+        assert_eq!(assets.len(), count);
+        for (i, asset) in assets.iter().enumerate() {
+            assert_eq!(asset.get_base(), format!("{:05}", i));
+            assert_eq!(asset.get_textures().len(), 2);
+            assert_eq!(
+                asset.get_texture_path(&"ao"),
+                Some(&PathBuf::from(format!("./{:05}_ao.png", i)))
+            );
+        }
+    }
+
+    #[test]
+    fn folder_reader() {
+        let files: Vec<PathBuf> = [
+            "./assets/bricks/brick_01/albedo.png",
+            "./assets/bricks/brick_01/roughness.png",
+            "./assets/bricks/brick_02/textures/albedo.png",
+            "./assets/bricks/brick_02/roughness.png",
+            "./assets/readme.txt",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        let reader = FolderAssetReader::new()
+            .add_matcher(Box::new(SuffixMatcher::new("albedo", "albedo")))
+            .add_matcher(Box::new(SuffixMatcher::new("roughness", "roughness")));
+        let assets = reader.resolve(&files);
+        let bases: Vec<&str> = assets.iter().map(|a| a.get_base()).collect();
+        assert_eq!(bases, vec!["brick_01", "brick_02", "textures"]);
+        assert_eq!(assets[0].get_textures().len(), 2);
+
+        let reader = reader
+            .set_root(PathBuf::from("./assets"))
+            .set_base(regex::Regex::new(r"^[^/]+/([^/]+)/").unwrap());
+        let assets = reader.resolve(&files);
+        let bases: Vec<&str> = assets.iter().map(|a| a.get_base()).collect();
+        assert_eq!(bases, vec!["brick_01", "brick_02"]);
+        assert_eq!(
+            assets[1].get_texture_path(&"albedo"),
+            Some(&PathBuf::from(
+                "./assets/bricks/brick_02/textures/albedo.png"
+            ))
+        );
+        assert_eq!(
+            assets[1].get_folder(),
+            Some(std::path::Path::new("./assets/bricks/brick_02"))
+        );
     }
-    // Retains only files, so that the resolver doesn't take care of discarding
-    // paths pointing to directory.
-    files.retain(|p| p.is_file());
-    out.append(&mut resolver.resolve(&files));
-    Ok(())
 }
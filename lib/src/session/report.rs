@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use crate::errors::ErrorKind;
+
+/// How a session run ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunStatus {
+    /// Every texture has been processed, successfully or not.
+    Completed,
+    /// The run has been stopped by a
+    /// [`CancellationToken`](super::CancellationToken) before processing every
+    /// texture.
+    Cancelled,
+}
+
+/// Result of [`Session::run`](super::Session::run).
+#[derive(Debug)]
+pub struct SessionReport {
+    pub status: RunStatus,
+
+    /// Paths of the textures written to disk.
+    pub written: Vec<PathBuf>,
+
+    /// Paths of the textures skipped because they were up to date.
+    pub skipped: Vec<PathBuf>,
+
+    /// Errors raised while generating or saving textures.
+    pub errors: Vec<SessionError>,
+
+    /// Textures whose asset lacks some inputs.
+    pub missing: Vec<MissingInputs>,
+}
+
+impl SessionReport {
+    /// Returns `true` if the run has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.status == RunStatus::Cancelled
+    }
+
+    /// Returns the base names of the assets lacking inputs, sorted and without
+    /// duplicates.
+    pub fn assets_with_missing_inputs(&self) -> Vec<&str> {
+        let mut assets: Vec<&str> = self.missing.iter().map(|m| m.asset.as_str()).collect();
+        assets.sort_unstable();
+        assets.dedup();
+        assets
+    }
+}
+
+/// Inputs of a texture that can't be found in its asset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissingInputs {
+    /// Base name of the asset.
+    pub asset: String,
+
+    /// Name of the target.
+    pub target: String,
+
+    /// Path the texture is written to.
+    pub output: PathBuf,
+
+    /// Identifiers of the missing required inputs. The texture isn't generated
+    /// when there is any.
+    pub required: Vec<String>,
+
+    /// Identifiers of the missing optional inputs, replaced by their fallback
+    /// value.
+    pub optional: Vec<String>,
+}
+
+/// Describes what a session does with a texture whose required inputs are
+/// missing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingInputPolicy {
+    /// The texture isn't generated, and a
+    /// [`SessionEvent::TargetSkipped`](super::SessionEvent::TargetSkipped) is
+    /// sent.
+    #[default]
+    Skip,
+    /// The texture fails with [`ErrorKind::MissingInputs`].
+    Fail,
+}
+
+/// Error raised by a session, with the context it happened in.
+#[derive(Debug)]
+pub struct SessionError {
+    /// Base name of the asset being processed, if any.
+    pub asset: Option<String>,
+
+    /// Name of the target being generated, if any.
+    pub target: Option<String>,
+
+    /// Files read to generate the texture.
+    pub inputs: Vec<PathBuf>,
+
+    /// Path of the file being written, if any.
+    pub output: Option<PathBuf>,
+
+    pub kind: ErrorKind,
+}
+
+impl SessionError {
+    /// Creates an error that isn't related to any asset nor target.
+    pub fn new(kind: ErrorKind) -> SessionError {
+        SessionError {
+            asset: None,
+            target: None,
+            inputs: Vec::new(),
+            output: None,
+            kind,
+        }
+    }
+}
+
+impl std::error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+impl std::fmt::Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(asset) = &self.asset {
+            write!(f, "asset '{}': ", asset)?;
+        }
+        if let Some(target) = &self.target {
+            write!(f, "target '{}': ", target)?;
+        }
+        write!(f, "{}", self.kind)?;
+        if let Some(output) = &self.output {
+            write!(f, " (output '{}')", output.display())?;
+        }
+        if !self.inputs.is_empty() {
+            let inputs: Vec<String> = self
+                .inputs
+                .iter()
+                .map(|p| format!("'{}'", p.display()))
+                .collect();
+            write!(f, " (inputs {})", inputs.join(", "))?;
+        }
+        Ok(())
+    }
+}
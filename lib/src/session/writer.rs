@@ -1,32 +1,167 @@
 use std::cmp::Eq;
+use std::fmt::{Debug, Display};
 use std::hash::Hash;
+use std::path::PathBuf;
 
+use image::imageops::FilterType;
 use image::{DynamicImage, ImageFormat};
 
 use crate::errors::ErrorKind;
-use crate::session::{Asset, GenericAsset};
-use crate::swizzle::{to_luma_a_dyn, to_luma_dyn, to_rgb_dyn, to_rgba_dyn, ChannelDescriptor};
+use crate::session::{Asset, GenericAsset, ImageCache};
+use crate::swizzle::{
+    to_dynamic_with_options, BitDepth, ChannelDescriptor, ChannelSource, ResizePolicy,
+    SwizzleOptions,
+};
+use crate::transform::Transform;
 
 /// Generalized texture target.
 ///
 /// Describes how to generate the final image, from a given [`Asset`].
+///
+/// Only [`Target::generate`], [`Target::get_filename`] and
+/// [`Target::get_format`] are required. The other methods have defaults
+/// disabling the features relying on them: incremental builds and missing
+/// inputs reports.
+///
+/// Since `0.2.0`, [`Target::generate`] receives the [`ImageCache`] used to
+/// decode the sources, and implementations written for `0.1` need to add
+/// this argument.
 pub trait Target<A: Asset> {
     type Identifier: Hash + Eq;
 
     /// Generates the texture by swizzling channels of inputs found in the
     /// ```asset``` container.
-    fn generate(&self, asset: &A) -> Result<DynamicImage, ErrorKind>;
+    ///
+    /// Source images should be decoded through ```images```, so that textures
+    /// used by several targets are only decoded once.
+    fn generate(&self, asset: &A, images: &ImageCache) -> Result<DynamicImage, ErrorKind>;
+
+    /// Returns the name of the target, used to report errors.
+    ///
+    /// Defaults to an empty name. Errors still report the path of the
+    /// texture.
+    fn get_name(&self) -> String {
+        String::new()
+    }
 
     /// Returns the file name the generated texture should have.
     fn get_filename(&self, asset: &A) -> String;
 
     /// Returns the encoding format this target will use when saved to disk.
     fn get_format(&self) -> ImageFormat;
+
+    /// Returns the paths of the files read to generate the texture of
+    /// ```asset```.
+    ///
+    /// The session uses them to skip textures whose inputs didn't change.
+    /// Defaults to no input.
+    fn get_inputs(&self, _asset: &A) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Returns the identifiers of the required inputs that can't be found in
+    /// ```asset```. The texture can't be generated without them.
+    ///
+    /// Defaults to no missing input, leaving [`Target::generate`] report
+    /// them.
+    fn get_missing_inputs(&self, _asset: &A) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns the identifiers of the optional inputs that can't be found in
+    /// ```asset```. They are replaced by a fallback value when generating the
+    /// texture.
+    ///
+    /// Defaults to no missing input.
+    fn get_missing_optional_inputs(&self, _asset: &A) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Returns a description of the target definition. It should change
+    /// whenever the generated texture could change for the same inputs.
+    ///
+    /// Textures of targets with an empty fingerprint, the default, are
+    /// generated on every run.
+    fn get_fingerprint(&self) -> String {
+        String::new()
+    }
+}
+
+/// Input of a [`GenericTarget`], used to fill one channel of the texture.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TargetInput<Identifier> {
+    /// Reads the channel `channel` of the texture matched under `id`, and
+    /// applies the given transforms on it.
+    ///
+    /// The input is required, unless it has a `fallback` value used when the
+    /// asset doesn't have the texture.
+    Texture {
+        id: Identifier,
+        channel: u8,
+        transforms: Vec<Transform>,
+        fallback: Option<f32>,
+    },
+    /// Fills the channel with a constant, normalized in the range `[0, 1]`.
+    Constant(f32),
+}
+
+impl<I> From<(I, u8)> for TargetInput<I> {
+    fn from(input: (I, u8)) -> Self {
+        TargetInput::Texture {
+            id: input.0,
+            channel: input.1,
+            transforms: Vec::new(),
+            fallback: None,
+        }
+    }
+}
+
+impl<I> From<(I, u8, Vec<Transform>)> for TargetInput<I> {
+    fn from(input: (I, u8, Vec<Transform>)) -> Self {
+        TargetInput::Texture {
+            id: input.0,
+            channel: input.1,
+            transforms: input.2,
+            fallback: None,
+        }
+    }
+}
+
+impl<I> TargetInput<I> {
+    /// Makes a texture input optional. The channel is filled with `value`,
+    /// normalized in the range `[0, 1]`, when the asset doesn't have the
+    /// texture.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use swizzler::session::TargetInput;
+    ///
+    /// // Fully occluded when the asset has no ambient occlusion map.
+    /// let ao = TargetInput::from(("ao", 0)).set_fallback(1.0);
+    /// ```
+    pub fn set_fallback(self, value: f32) -> Self {
+        match self {
+            TargetInput::Texture {
+                id,
+                channel,
+                transforms,
+                ..
+            } => TargetInput::Texture {
+                id,
+                channel,
+                transforms,
+                fallback: Some(value),
+            },
+            constant => constant,
+        }
+    }
 }
 
 /// Generic implementation of the [`Target`] trait.
 ///
 /// This allows to create target at runtime, from a config file for instance.
+#[derive(Debug)]
 pub struct GenericTarget<Identifier: Eq + Hash + Sync = String> {
     /// Name to append when generating the filename.
     pub name: Option<String>,
@@ -34,16 +169,28 @@ pub struct GenericTarget<Identifier: Eq + Hash + Sync = String> {
     /// Format to use when encoding the texture.
     pub output_format: image::ImageFormat,
 
+    /// Number of bits per channel of the generated texture.
+    pub bit_depth: BitDepth,
+
+    /// Policy used when inputs don't share the same dimensions.
+    pub resize: ResizePolicy,
+
+    /// Filter used when inputs are resized.
+    pub resize_filter: FilterType,
+
     /// Swizzling inputs.
-    pub inputs: Vec<Option<(Identifier, u8)>>,
+    pub inputs: Vec<Option<TargetInput<Identifier>>>,
 }
 
 impl<I: Eq + Hash + Sync> GenericTarget<I> {
-    pub fn new(inputs: Vec<Option<(I, u8)>>) -> GenericTarget<I> {
+    pub fn new<T: Into<TargetInput<I>>>(inputs: Vec<Option<T>>) -> GenericTarget<I> {
         GenericTarget {
             name: None,
-            output_format: ImageFormat::PNG,
-            inputs,
+            output_format: ImageFormat::Png,
+            bit_depth: BitDepth::U8,
+            resize: ResizePolicy::Error,
+            resize_filter: FilterType::Triangle,
+            inputs: inputs.into_iter().map(|i| i.map(Into::into)).collect(),
         }
     }
 
@@ -57,48 +204,100 @@ impl<I: Eq + Hash + Sync> GenericTarget<I> {
         self
     }
 
-    fn _create_descriptor(
+    pub fn set_bit_depth(mut self, depth: BitDepth) -> Self {
+        self.bit_depth = depth;
+        self
+    }
+
+    pub fn set_resize(mut self, policy: ResizePolicy) -> Self {
+        self.resize = policy;
+        self
+    }
+
+    pub fn set_resize_filter(mut self, filter: FilterType) -> Self {
+        self.resize_filter = filter;
+        self
+    }
+
+    /// Returns the identifiers of the required, or optional, inputs that
+    /// can't be found in ```asset```.
+    fn _missing_inputs(&self, asset: &GenericAsset<I>, optional: bool) -> Vec<String>
+    where
+        I: Display,
+    {
+        let mut missing: Vec<String> = Vec::new();
+        for input in self.inputs.iter().flatten() {
+            if let TargetInput::Texture { id, fallback, .. } = input {
+                if fallback.is_some() == optional && asset.get_texture_path(id).is_none() {
+                    let id = id.to_string();
+                    if !missing.contains(&id) {
+                        missing.push(id);
+                    }
+                }
+            }
+        }
+        missing
+    }
+
+    fn _create_source(
         &self,
         index: usize,
         asset: &GenericAsset<I>,
-    ) -> Result<Option<ChannelDescriptor>, ErrorKind> {
-        if let Some(input) = &self.inputs[index] {
-            match asset.get_texture_path(&input.0) {
-                Some(path) => Ok(Some(ChannelDescriptor::from_path(path, input.1)?)),
-                _ => Ok(None),
-            }
-        } else {
-            Ok(None)
+        images: &ImageCache,
+    ) -> Result<Option<ChannelSource>, ErrorKind> {
+        match &self.inputs[index] {
+            Some(TargetInput::Texture {
+                id,
+                channel,
+                transforms,
+                fallback,
+            }) => match asset.get_texture_path(id) {
+                Some(path) => Ok(Some(
+                    ChannelDescriptor::from_image_arc(&images.get(path)?, *channel)?
+                        .set_transforms(transforms.clone())
+                        .into(),
+                )),
+                _ => Ok(fallback.map(ChannelSource::Constant)),
+            },
+            Some(TargetInput::Constant(value)) => Ok(Some(ChannelSource::Constant(*value))),
+            None => Ok(None),
         }
     }
 }
 
-impl<'a, I: Hash + Eq + Sync + 'a> Target<GenericAsset<'a, I>> for GenericTarget<I> {
+impl<'a, I: Hash + Eq + Sync + Debug + Display + 'a> Target<GenericAsset<'a, I>>
+    for GenericTarget<I>
+{
     type Identifier = I;
 
-    fn generate(&self, asset: &GenericAsset<'a, I>) -> Result<DynamicImage, ErrorKind> {
-        match self.inputs.len() {
-            1 => match &self._create_descriptor(0, asset)? {
-                Some(d) => to_luma_dyn(d),
-                None => Err(ErrorKind::EmptyDescriptor),
-            },
-            2 => to_luma_a_dyn(
-                &self._create_descriptor(0, asset)?,
-                &self._create_descriptor(1, asset)?,
-            ),
-            3 => to_rgb_dyn(
-                &self._create_descriptor(0, asset)?,
-                &self._create_descriptor(1, asset)?,
-                &self._create_descriptor(2, asset)?,
-            ),
-            a if a >= 4 => to_rgba_dyn(
-                &self._create_descriptor(0, asset)?,
-                &self._create_descriptor(1, asset)?,
-                &self._create_descriptor(2, asset)?,
-                &self._create_descriptor(3, asset)?,
-            ),
-            _ => panic!("too big vector!"),
+    fn generate(
+        &self,
+        asset: &GenericAsset<'a, I>,
+        images: &ImageCache,
+    ) -> Result<DynamicImage, ErrorKind> {
+        // Fails before decoding any source.
+        if self.inputs.is_empty() || self.inputs.len() > 4 {
+            return Err(ErrorKind::InvalidChannelCount(self.inputs.len()));
         }
+        let missing = self.get_missing_inputs(asset);
+        if !missing.is_empty() {
+            return Err(ErrorKind::MissingInputs(missing));
+        }
+        let sources = (0..self.inputs.len())
+            .map(|i| self._create_source(i, asset, images))
+            .collect::<Result<Vec<Option<ChannelSource>>, ErrorKind>>()?;
+        let options = SwizzleOptions {
+            bit_depth: self.bit_depth,
+            resize: self.resize,
+            resize_filter: self.resize_filter,
+            // Sessions already generate targets in parallel.
+            threads: 1,
+        };
+        to_dynamic_with_options(&sources, &options)
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone().unwrap_or_default()
     }
 
     fn get_filename(&self, asset: &GenericAsset<'a, I>) -> String {
@@ -112,4 +311,103 @@ impl<'a, I: Hash + Eq + Sync + 'a> Target<GenericAsset<'a, I>> for GenericTarget
     fn get_format(&self) -> ImageFormat {
         self.output_format
     }
+
+    fn get_inputs(&self, asset: &GenericAsset<'a, I>) -> Vec<PathBuf> {
+        let mut inputs: Vec<PathBuf> = Vec::new();
+        for input in self.inputs.iter().flatten() {
+            if let TargetInput::Texture { id, .. } = input {
+                if let Some(path) = asset.get_texture_path(id) {
+                    if !inputs.contains(path) {
+                        inputs.push(path.to_path_buf());
+                    }
+                }
+            }
+        }
+        inputs
+    }
+
+    fn get_missing_inputs(&self, asset: &GenericAsset<'a, I>) -> Vec<String> {
+        self._missing_inputs(asset, false)
+    }
+
+    fn get_missing_optional_inputs(&self, asset: &GenericAsset<'a, I>) -> Vec<String> {
+        self._missing_inputs(asset, true)
+    }
+
+    /// Describes every field affecting the generated texture, in a format
+    /// that doesn't depend on how the target is implemented:
+    ///
+    /// `v1;<format>;<bit depth>;<resize>;<filter>;<inputs>;<name>`
+    fn get_fingerprint(&self) -> String {
+        let format = self.output_format.extensions_str().first().unwrap_or(&"");
+        let depth = match self.bit_depth {
+            BitDepth::U8 => "u8",
+            BitDepth::U16 => "u16",
+            BitDepth::F32 => "f32",
+        };
+        let resize = match self.resize {
+            ResizePolicy::Error => String::from("error"),
+            ResizePolicy::Largest => String::from("largest"),
+            ResizePolicy::Smallest => String::from("smallest"),
+            ResizePolicy::Exact(width, height) => format!("{}x{}", width, height),
+        };
+        let filter = match self.resize_filter {
+            FilterType::Nearest => "nearest",
+            FilterType::Triangle => "triangle",
+            FilterType::CatmullRom => "catmullrom",
+            FilterType::Gaussian => "gaussian",
+            FilterType::Lanczos3 => "lanczos3",
+        };
+        let inputs: Vec<String> = self
+            .inputs
+            .iter()
+            .map(|input| match input {
+                Some(TargetInput::Texture {
+                    id,
+                    channel,
+                    transforms,
+                    fallback,
+                }) => {
+                    let mut desc = format!("{}:{}", escape(&id.to_string()), channel);
+                    for transform in transforms {
+                        desc.push_str(&format!(":{}", transform));
+                    }
+                    if let Some(value) = fallback {
+                        desc.push_str(&format!(":fallback={}", value));
+                    }
+                    desc
+                }
+                Some(TargetInput::Constant(value)) => format!("constant={}", value),
+                None => String::from("none"),
+            })
+            .collect();
+        format!(
+            "v1;{};{};{};{};[{}];{}",
+            format,
+            depth,
+            resize,
+            filter,
+            inputs.join(","),
+            escape(&self.get_name())
+        )
+    }
+}
+
+/// Escapes the characters used as separators in fingerprints and in the
+/// build manifest.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ':' | ';' | ',' | '[' | ']' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
 }
@@ -1,7 +1,10 @@
 use crate::errors::ErrorKind;
-use image::{DynamicImage, Luma, LumaA, Rgb, Rgba};
-
-use paste;
+use crate::transform::{apply_all, Transform};
+use image::imageops::FilterType;
+use image::{
+    DynamicImage, FlatSamples, GenericImageView, ImageBuffer, Luma, LumaA, Pixel, Rgb, Rgba,
+};
+use std::borrow::Cow;
 
 type SwizzleResult<T> = Result<T, ErrorKind>;
 type SwizzleResultDyn = Result<DynamicImage, ErrorKind>;
@@ -12,6 +15,10 @@ type ChannelDescResult = Result<ChannelDescriptor, ErrorKind>;
 /// This type provides an pointer to an allocated image, as well as a channel
 /// index, representing the source to read from the image.
 ///
+/// The image is reference counted using an `Arc`, making descriptors
+/// `Send` and `Sync`: descriptors sharing the same image can be swizzled in
+/// parallel.
+///
 /// Channel descriptors are used to feed swizzling. They describe how to extract
 /// source pixel data.
 ///
@@ -32,7 +39,19 @@ type ChannelDescResult = Result<ChannelDescriptor, ErrorKind>;
 /// ```
 pub struct ChannelDescriptor {
     pub channel: u8,
-    pub img: std::rc::Rc<image::DynamicImage>,
+    pub img: std::sync::Arc<image::DynamicImage>,
+
+    /// Transforms applied, in order, on the values read from the channel.
+    pub transforms: Vec<Transform>,
+}
+
+/// Returns an error if `img` has no channel `channel`.
+fn check_channel(img: &DynamicImage, channel: u8) -> Result<(), ErrorKind> {
+    let channels = img.color().channel_count();
+    if channel >= channels {
+        return Err(ErrorKind::InvalidChannel { channel, channels });
+    }
+    Ok(())
 }
 
 impl Clone for ChannelDescriptor {
@@ -40,23 +59,35 @@ impl Clone for ChannelDescriptor {
         ChannelDescriptor {
             channel: self.channel,
             img: self.img.clone(),
+            transforms: self.transforms.clone(),
         }
     }
 }
 
 impl ChannelDescriptor {
-    /// Generates a descriptor from an image RC pointer and a channel.
+    /// Generates a descriptor from an image Arc pointer and a channel.
+    ///
+    /// Use this to share a decoded image between several descriptors,
+    /// possibly living on different threads.
     ///
     /// # Arguments
     ///
-    /// * `img_input` - RC pointer to the image source
+    /// * `img_input` - Arc pointer to the image source
     /// * `channel` - Source channel in the given input source
-    pub fn from_image_rc(
-        img_input: &std::rc::Rc<image::DynamicImage>,
+    ///
+    /// Fails with [`ErrorKind::InvalidChannel`] if the image has no such
+    /// channel.
+    pub fn from_image_arc(
+        img_input: &std::sync::Arc<image::DynamicImage>,
         channel: u8,
     ) -> ChannelDescResult {
-        let img = std::rc::Rc::clone(img_input);
-        Ok(ChannelDescriptor { img: img, channel })
+        check_channel(img_input, channel)?;
+        let img = std::sync::Arc::clone(img_input);
+        Ok(ChannelDescriptor {
+            img,
+            channel,
+            transforms: Vec::new(),
+        })
     }
 
     /// Generates a descriptor from an image and a channel.
@@ -65,9 +96,17 @@ impl ChannelDescriptor {
     ///
     /// * `img_input` - Image source
     /// * `channel` - Source channel in the given input source
+    ///
+    /// Fails with [`ErrorKind::InvalidChannel`] if the image has no such
+    /// channel.
     pub fn from_image(img_input: image::DynamicImage, channel: u8) -> ChannelDescResult {
-        let img = std::rc::Rc::new(img_input);
-        Ok(ChannelDescriptor { img, channel })
+        check_channel(&img_input, channel)?;
+        let img = std::sync::Arc::new(img_input);
+        Ok(ChannelDescriptor {
+            img,
+            channel,
+            transforms: Vec::new(),
+        })
     }
 
     /// Generates a descriptor from a path and a channel.
@@ -98,10 +137,14 @@ impl ChannelDescriptor {
     /// Generates a descriptor from a string containing the path to the image
     /// source, to which is appended the channel to read.
     ///
+    /// The channel can optionally be followed by a list of transforms, each
+    /// one prefixed by the separator `:`. Take a look at [`Transform`] for the
+    /// list of available transforms.
+    ///
     /// # Arguments
     ///
     /// * `input` - String containing the path to the image, followed by the
-    /// separator `:` and the channel to read
+    ///   separator `:` and the channel to read
     ///
     /// # Examples
     ///
@@ -111,6 +154,10 @@ impl ChannelDescriptor {
     /// // Creates a descriptor pointing to file "./input.png", and set it up
     /// // to read its `red` channel (channel 0).
     /// let descriptor = ChannelDescriptor::from_description("./input.png:0");
+    ///
+    /// // Creates a descriptor reading the `red` channel of file "./rough.png",
+    /// // and inverting it.
+    /// let descriptor = ChannelDescriptor::from_description("./rough.png:0:invert");
     /// ```
     pub fn from_description<T>(input: T) -> ChannelDescResult
     where
@@ -131,90 +178,609 @@ impl ChannelDescriptor {
         let channel = (last.parse::<u8>().map_err(|_e| -> ErrorKind {
             ErrorKind::InvalidDescriptorString(String::from("failed to parse channel"))
         }))?;
+        let transforms = split
+            .map(|t| t.parse::<Transform>())
+            .collect::<Result<Vec<Transform>, ErrorKind>>()?;
         let img = image::open(&img_path)?;
 
-        ChannelDescriptor::from_image(img, channel)
+        Ok(ChannelDescriptor::from_image(img, channel)?.set_transforms(transforms))
+    }
+
+    /// Adds a transform, applied after all previously added transforms.
+    pub fn add_transform(mut self, transform: Transform) -> Self {
+        self.transforms.push(transform);
+        self
+    }
+
+    /// Replaces the list of transforms applied on the values read.
+    pub fn set_transforms(mut self, transforms: Vec<Transform>) -> Self {
+        self.transforms = transforms;
+        self
     }
 }
 
-/// Macro swizzling multiple descriptors into a new image.
+/// Source used to fill a channel of a swizzled image.
 ///
-/// The maccro uses a default value, to which each pixel will be set to prior
-/// to swizzling the sources.
+/// A source either reads a channel of an image, using a [`ChannelDescriptor`],
+/// or fills the channel with a constant.
 ///
-/// It also uses a set of sources, that will get swizzled into the result image.
-macro_rules! swizzle {
-    ( $p:expr, $( $x:ident ),* ) => {
-        paste::expr! {
-            let mut dimensions: Option<(u32, u32)> = None;
-            $(
-                // Saves source channel into a variable `r_channel`, etc...
-                let [<$x _channel>]: u8 = match $x {
-                    Some(desc) => desc.channel,
-                    None => 0
-                };
-                // Saves flat samples into a variable `r_flat`, etc...
-                let [<$x _flat>] = match $x {
-                    Some(desc) => Some(desc.img.as_ref().as_flat_samples()),
-                    None => None
-                };
-                // Validates that all input images have the same dimensions.
-                if let Some(sample) = &[<$x _flat>] {
-                    let img_dim = (sample.layout.width, sample.layout.height);
-                    dimensions.get_or_insert(img_dim);
-                    if img_dim != dimensions.unwrap() {
-                        return Err(ErrorKind::InvalidSize);
-                    }
+/// # Examples
+///
+/// ```
+/// use swizzler::{ChannelDescriptor, ChannelSource};
+///
+/// // Fills the channel with a mid gray, i.e `128` for a 8 bits image.
+/// let constant = ChannelSource::Constant(0.5);
+///
+/// // Reads the `red` channel of file "./input.png".
+/// let source = ChannelDescriptor::from_path("./input.png", 0).map(ChannelSource::from);
+/// ```
+#[derive(Clone)]
+pub enum ChannelSource {
+    /// Reads the channel described by the descriptor.
+    Descriptor(ChannelDescriptor),
+    /// Fills the channel with a constant, normalized in the range `[0, 1]`.
+    Constant(f32),
+}
 
-                }
-            )*
-
-            // Creates result image.
-            let dimensions = dimensions.ok_or(ErrorKind::InvalidSize)?;
-            let mut image = image::ImageBuffer::from_pixel(
-                dimensions.0,
-                dimensions.1,
-                $p
-            );
+impl From<ChannelDescriptor> for ChannelSource {
+    fn from(descriptor: ChannelDescriptor) -> Self {
+        ChannelSource::Descriptor(descriptor)
+    }
+}
 
-            // Copies source pixels into result image
-            let pixels = image.enumerate_pixels_mut();
-            let mut i = 0; // TODO: change to recursive macro to compute index.
-            for (x, y, pixel) in pixels {
-                $(
-                    if let Some(sample) = &[<$x _flat>] {
-                        pixel[i] = *sample.get_sample(
-                            [<$x _channel>], x, y
-                        ).unwrap();
-                    }
-                    i += 1;
-                )*
-                i = 0;
+impl ChannelSource {
+    /// Generates a source from a string containing either a constant, or a
+    /// descriptor string as expected by [`ChannelDescriptor::from_description`].
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - String containing a constant normalized in the range
+    ///   `[0, 1]`, or the path to an image followed by the separator `:` and
+    ///   the channel to read
+    ///
+    /// Numbers outside of the range `[0, 1]`, as well as infinite and `NaN`
+    /// values, are rejected.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use swizzler::ChannelSource;
+    ///
+    /// // Creates a source filling the channel with `0.5`.
+    /// let source = ChannelSource::from_description("0.5");
+    /// ```
+    pub fn from_description<T>(input: T) -> Result<ChannelSource, ErrorKind>
+    where
+        T: AsRef<str>,
+    {
+        match input.as_ref().parse::<f32>() {
+            Ok(value) if (0.0..=1.0).contains(&value) => Ok(ChannelSource::Constant(value)),
+            Ok(_) => Err(ErrorKind::InvalidDescriptorString(format!(
+                "constant '{}' isn't in the range [0, 1]",
+                input.as_ref()
+            ))),
+            Err(_) => Ok(ChannelSource::Descriptor(
+                ChannelDescriptor::from_description(input)?,
+            )),
+        }
+    }
+}
+
+/// _Grayscale_ image with 16 bits per channel.
+pub type Gray16Image = ImageBuffer<Luma<u16>, Vec<u16>>;
+
+/// _Luminance Alpha_ image with 16 bits per channel.
+pub type GrayAlpha16Image = ImageBuffer<LumaA<u16>, Vec<u16>>;
+
+/// _RGB_ image with 16 bits per channel.
+pub type Rgb16Image = ImageBuffer<Rgb<u16>, Vec<u16>>;
+
+/// _RGBA_ image with 16 bits per channel.
+pub type Rgba16Image = ImageBuffer<Rgba<u16>, Vec<u16>>;
+
+/// _Grayscale_ image with a 32 bits float per channel.
+pub type Gray32FImage = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+/// _Luminance Alpha_ image with a 32 bits float per channel.
+pub type GrayAlpha32FImage = ImageBuffer<LumaA<f32>, Vec<f32>>;
+
+/// Number of bits used to store each channel of a swizzled image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    /// 8 bits per channel.
+    #[default]
+    U8,
+    /// 16 bits per channel.
+    U16,
+    /// 32 bits float per channel.
+    F32,
+}
+
+/// Policy used when the sources to swizzle don't share the same dimensions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ResizePolicy {
+    /// Fails with [`ErrorKind::InvalidSize`].
+    #[default]
+    Error,
+    /// Resizes all sources to the largest width and largest height found.
+    Largest,
+    /// Resizes all sources to the smallest width and smallest height found.
+    Smallest,
+    /// Resizes all sources to the given width and height.
+    Exact(u32, u32),
+}
+
+/// Subpixel type a swizzled image can be made of.
+///
+/// Sources are converted to the subpixel type of the output when read:
+/// * 8 bits values are widened by replicating their bits, i.e `0xAB` becomes
+///   `0xABAB`, so that `255` maps to `65535`
+/// * 16 bits values are narrowed by rounding to the nearest 8 bits value
+/// * Integer values are normalized to the range `[0, 1]` when read as floats
+/// * Float values are clamped to the range `[0, 1]` and quantized when read as
+///   integers
+trait Subpixel: image::Primitive + Default + Send + Sync {
+    fn from_u8(value: u8) -> Self;
+    fn from_u16(value: u16) -> Self;
+    fn from_f32(value: f32) -> Self;
+}
+
+impl Subpixel for u8 {
+    fn from_u8(value: u8) -> Self {
+        value
+    }
+
+    fn from_u16(value: u16) -> Self {
+        ((u32::from(value) * 255 + 32767) / 65535) as u8
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+impl Subpixel for u16 {
+    fn from_u8(value: u8) -> Self {
+        u16::from(value) * 257
+    }
+
+    fn from_u16(value: u16) -> Self {
+        value
+    }
+
+    fn from_f32(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 65535.0).round() as u16
+    }
+}
+
+impl Subpixel for f32 {
+    fn from_u8(value: u8) -> Self {
+        f32::from(value) / 255.0
+    }
+
+    fn from_u16(value: u16) -> Self {
+        f32::from(value) / 65535.0
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value
+    }
+}
+
+/// Flat view over the subpixels of a source image.
+enum Samples<'a> {
+    U8(FlatSamples<&'a [u8]>),
+    U16(FlatSamples<&'a [u16]>),
+    F32(FlatSamples<&'a [f32]>),
+}
+
+impl<'a> Samples<'a> {
+    /// Returns the source image if its subpixels can be read directly, or a
+    /// 32 bits float copy of it otherwise.
+    fn readable(img: &DynamicImage) -> Cow<'_, DynamicImage> {
+        if img.as_flat_samples_u8().is_some()
+            || img.as_flat_samples_u16().is_some()
+            || img.as_flat_samples_f32().is_some()
+        {
+            Cow::Borrowed(img)
+        } else {
+            Cow::Owned(DynamicImage::ImageRgba32F(img.to_rgba32f()))
+        }
+    }
+
+    /// Creates a view over an image previously returned by [`Samples::readable`].
+    fn new(img: &'a DynamicImage) -> Samples<'a> {
+        if let Some(flat) = img.as_flat_samples_u8() {
+            Samples::U8(flat)
+        } else if let Some(flat) = img.as_flat_samples_u16() {
+            Samples::U16(flat)
+        } else {
+            Samples::F32(img.as_flat_samples_f32().unwrap())
+        }
+    }
+
+    fn channels(&self) -> u8 {
+        match self {
+            Samples::U8(flat) => flat.layout.channels,
+            Samples::U16(flat) => flat.layout.channels,
+            Samples::F32(flat) => flat.layout.channels,
+        }
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Samples::U8(flat) => (flat.layout.width, flat.layout.height),
+            Samples::U16(flat) => (flat.layout.width, flat.layout.height),
+            Samples::F32(flat) => (flat.layout.width, flat.layout.height),
+        }
+    }
+
+    /// Reads row `y` of the given channel, and writes it into channel `index`
+    /// of `dst`, a row of `dst_channels` interleaved channels.
+    fn swizzle_row<T: Subpixel>(
+        &self,
+        channel: u8,
+        transforms: &[Transform],
+        y: usize,
+        dst: &mut [T],
+        dst_channels: usize,
+        index: usize,
+    ) {
+        match self {
+            Samples::U8(flat) => read_row(flat, channel, transforms, y, dst, dst_channels, index),
+            Samples::U16(flat) => read_row(flat, channel, transforms, y, dst, dst_channels, index),
+            Samples::F32(flat) => read_row(flat, channel, transforms, y, dst, dst_channels, index),
+        }
+    }
+}
+
+/// Subpixel type a source image can be read from.
+trait SourceSample: Copy {
+    fn convert<T: Subpixel>(self) -> T;
+}
+
+impl SourceSample for u8 {
+    fn convert<T: Subpixel>(self) -> T {
+        T::from_u8(self)
+    }
+}
+
+impl SourceSample for u16 {
+    fn convert<T: Subpixel>(self) -> T {
+        T::from_u16(self)
+    }
+}
+
+impl SourceSample for f32 {
+    fn convert<T: Subpixel>(self) -> T {
+        T::from_f32(self)
+    }
+}
+
+/// Copies row `y` of a source channel into channel `index` of `dst`.
+///
+/// Packed sources, i.e sources whose channels are interleaved with no
+/// padding, are read as contiguous pixels. Other layouts go through their
+/// strides.
+fn read_row<S: SourceSample, T: Subpixel>(
+    flat: &FlatSamples<&[S]>,
+    channel: u8,
+    transforms: &[Transform],
+    y: usize,
+    dst: &mut [T],
+    dst_channels: usize,
+    index: usize,
+) {
+    let layout = &flat.layout;
+    let width = layout.width as usize;
+    let start = y * layout.height_stride + channel as usize * layout.channel_stride;
+    let dst = dst[index..].iter_mut().step_by(dst_channels);
+
+    let convert = |value: S| -> T {
+        if transforms.is_empty() {
+            value.convert()
+        } else {
+            T::from_f32(apply_all(transforms, value.convert()))
+        }
+    };
+
+    let packed = layout.channel_stride == 1 && layout.width_stride == layout.channels as usize;
+    if packed {
+        let row_start = y * layout.height_stride;
+        let row = &flat.samples[row_start..row_start + width * layout.width_stride];
+        let src = row
+            .chunks_exact(layout.width_stride)
+            .map(|px| px[channel as usize]);
+        for (d, value) in dst.zip(src) {
+            *d = convert(value);
+        }
+    } else {
+        let src = flat.samples[start..]
+            .iter()
+            .step_by(layout.width_stride.max(1))
+            .take(width);
+        for (d, value) in dst.zip(src) {
+            *d = convert(*value);
+        }
+    }
+}
+
+/// Layout of an image generated by [`swizzle`], i.e its number of channels
+/// and its number of bits per channel.
+///
+/// **NOTE**: `image::DynamicImage` has no _Grayscale_ float variants. Use
+/// [`to_luma32f`] and [`to_luma_a32f`] to generate such images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputLayout {
+    Luma8,
+    LumaA8,
+    Rgb8,
+    Rgba8,
+    Luma16,
+    LumaA16,
+    Rgb16,
+    Rgba16,
+    Rgb32F,
+    Rgba32F,
+}
+
+impl OutputLayout {
+    /// Returns the layout with the given number of channels and bit depth.
+    ///
+    /// Fails with [`ErrorKind::InvalidChannelCount`] if `channels` isn't in
+    /// the range `[1, 4]`, and with [`ErrorKind::UnsupportedLayout`] for float
+    /// _Grayscale_ layouts.
+    pub fn new(channels: usize, depth: BitDepth) -> SwizzleResult<OutputLayout> {
+        match (depth, channels) {
+            (BitDepth::U8, 1) => Ok(OutputLayout::Luma8),
+            (BitDepth::U8, 2) => Ok(OutputLayout::LumaA8),
+            (BitDepth::U8, 3) => Ok(OutputLayout::Rgb8),
+            (BitDepth::U8, 4) => Ok(OutputLayout::Rgba8),
+            (BitDepth::U16, 1) => Ok(OutputLayout::Luma16),
+            (BitDepth::U16, 2) => Ok(OutputLayout::LumaA16),
+            (BitDepth::U16, 3) => Ok(OutputLayout::Rgb16),
+            (BitDepth::U16, 4) => Ok(OutputLayout::Rgba16),
+            (BitDepth::F32, 1) | (BitDepth::F32, 2) => Err(ErrorKind::UnsupportedLayout(
+                String::from("float images must have three or four channels"),
+            )),
+            (BitDepth::F32, 3) => Ok(OutputLayout::Rgb32F),
+            (BitDepth::F32, 4) => Ok(OutputLayout::Rgba32F),
+            (_, n) => Err(ErrorKind::InvalidChannelCount(n)),
+        }
+    }
+
+    /// Returns the number of channels of the layout.
+    pub fn channel_count(&self) -> usize {
+        match self {
+            OutputLayout::Luma8 | OutputLayout::Luma16 => 1,
+            OutputLayout::LumaA8 | OutputLayout::LumaA16 => 2,
+            OutputLayout::Rgb8 | OutputLayout::Rgb16 | OutputLayout::Rgb32F => 3,
+            OutputLayout::Rgba8 | OutputLayout::Rgba16 | OutputLayout::Rgba32F => 4,
+        }
+    }
+
+    /// Returns the number of bits per channel of the layout.
+    pub fn bit_depth(&self) -> BitDepth {
+        match self {
+            OutputLayout::Luma8
+            | OutputLayout::LumaA8
+            | OutputLayout::Rgb8
+            | OutputLayout::Rgba8 => BitDepth::U8,
+            OutputLayout::Luma16
+            | OutputLayout::LumaA16
+            | OutputLayout::Rgb16
+            | OutputLayout::Rgba16 => BitDepth::U16,
+            OutputLayout::Rgb32F | OutputLayout::Rgba32F => BitDepth::F32,
+        }
+    }
+}
+
+/// Converts the optional sources of the typed functions into a vector.
+fn to_sources<S: Into<ChannelSource> + Clone>(
+    sources: &[&Option<S>],
+) -> Vec<Option<ChannelSource>> {
+    sources
+        .iter()
+        .map(|s| s.as_ref().map(|s| s.clone().into()))
+        .collect()
+}
+
+/// Swizzles the sources into a new image, one source per channel.
+///
+/// Each pixel is first set to `default`, into which constant sources are
+/// written. Channels with no source keep their default value.
+///
+/// The image is processed row by row. When `threads` is greater than one,
+/// rows are split between that many threads.
+fn swizzle_buffer<P>(
+    sources: &[Option<ChannelSource>],
+    default: P,
+    threads: usize,
+) -> SwizzleResult<ImageBuffer<P, Vec<P::Subpixel>>>
+where
+    P: Pixel,
+    P::Subpixel: Subpixel,
+{
+    if sources.is_empty() || sources.len() > P::CHANNEL_COUNT as usize {
+        return Err(ErrorKind::InvalidChannelCount(sources.len()));
+    }
+    if sources.iter().all(Option::is_none) {
+        return Err(ErrorKind::EmptyDescriptor);
+    }
+
+    // Writes constant sources into the default value.
+    let mut fill = default;
+    for (i, source) in sources.iter().enumerate() {
+        if let Some(ChannelSource::Constant(value)) = source {
+            fill.channels_mut()[i] = Subpixel::from_f32(*value);
+        }
+    }
+
+    let images: Vec<Option<Cow<DynamicImage>>> = sources
+        .iter()
+        .map(|source| match source {
+            Some(ChannelSource::Descriptor(desc)) => Some(Samples::readable(&desc.img)),
+            _ => None,
+        })
+        .collect();
+
+    // Validates all sources before processing any pixel.
+    let mut dimensions: Option<(u32, u32)> = None;
+    let mut readers: Vec<(usize, Samples, u8, &[Transform])> = Vec::new();
+    for (i, (source, img)) in sources.iter().zip(&images).enumerate() {
+        let (desc, img) = match (source, img) {
+            (Some(ChannelSource::Descriptor(desc)), Some(img)) => (desc, img),
+            _ => continue,
+        };
+        let samples = Samples::new(img);
+        // Descriptor fields are public, and may have been changed after
+        // creation.
+        if desc.channel >= samples.channels() {
+            return Err(ErrorKind::InvalidChannel {
+                channel: desc.channel,
+                channels: samples.channels(),
+            });
+        }
+        let img_dim = samples.dimensions();
+        let expected = *dimensions.get_or_insert(img_dim);
+        if img_dim != expected {
+            return Err(ErrorKind::InvalidSize {
+                expected,
+                actual: img_dim,
+            });
+        }
+        readers.push((i, samples, desc.channel, &desc.transforms));
+    }
+
+    // Constants alone don't give the size of the image.
+    let dimensions = dimensions.ok_or(ErrorKind::NoInputs)?;
+    let mut image = ImageBuffer::from_pixel(dimensions.0, dimensions.1, fill);
+
+    let (width, height) = dimensions;
+    let row_len = width as usize * P::CHANNEL_COUNT as usize;
+    if row_len == 0 || height == 0 {
+        return Ok(image);
+    }
+
+    // Copies source rows into the rows of the result image. Channels and
+    // dimensions are validated above, so rows are always complete.
+    let swizzle_rows = |first_row: usize, rows: &mut [P::Subpixel]| {
+        for (y, row) in rows.chunks_exact_mut(row_len).enumerate() {
+            for (i, samples, channel, transforms) in &readers {
+                samples.swizzle_row(
+                    *channel,
+                    transforms,
+                    first_row + y,
+                    row,
+                    P::CHANNEL_COUNT as usize,
+                    *i,
+                );
             }
-            Ok(image)
         }
     };
+
+    let buffer: &mut [P::Subpixel] = &mut image;
+    let threads = threads.clamp(1, height as usize);
+    if threads == 1 {
+        swizzle_rows(0, buffer);
+    } else {
+        // Splits rows evenly between threads.
+        let rows_per_thread = (height as usize).div_ceil(threads);
+        let swizzle_rows = &swizzle_rows;
+        crossbeam::scope(|scope| {
+            for (i, rows) in buffer.chunks_mut(rows_per_thread * row_len).enumerate() {
+                scope.spawn(move |_| swizzle_rows(i * rows_per_thread, rows));
+            }
+        })
+        .unwrap();
+    }
+    Ok(image)
 }
 
-/// Generates a _Grayscale_ image from a single descriptor
+/// Swizzles a list of sources into a new image with the given layout. This
+/// method allows to generate an image directly from runtime data.
+///
+/// Sources fill the channels in order. When there are fewer sources than
+/// channels, the remaining channels are left empty, i.e `0`, or fully opaque
+/// for the alpha channel.
 ///
 /// # Arguments
 ///
-/// * `r` - The descriptor to use for writing the _red_ channel
-pub fn to_luma(r: &ChannelDescriptor) -> SwizzleResult<image::GrayImage> {
-    static DEFAULT: Luma<u8> = Luma([0]);
-    let descriptor = Some(r);
-    swizzle!(DEFAULT, descriptor)
+/// * `sources` - Sources of each channel. `None` leaves the channel empty
+/// * `layout` - Layout of the generated image
+///
+/// # Examples
+///
+/// ```no_run
+/// use swizzler::{swizzle, ChannelSource, OutputLayout};
+///
+/// let sources = vec![
+///     Some(ChannelSource::from_description("./metalness.png:0").unwrap()),
+///     None,
+///     None,
+///     Some(ChannelSource::from_description("./roughness.png:0").unwrap()),
+/// ];
+/// let image = swizzle(&sources, OutputLayout::Rgba16);
+/// ```
+pub fn swizzle(sources: &[Option<ChannelSource>], layout: OutputLayout) -> SwizzleResultDyn {
+    swizzle_layout(sources, layout, 1)
+}
+
+/// Swizzles sources into a new image with the given layout, splitting rows
+/// between `threads` threads.
+fn swizzle_layout(
+    sources: &[Option<ChannelSource>],
+    layout: OutputLayout,
+    threads: usize,
+) -> SwizzleResultDyn {
+    if sources.len() > layout.channel_count() {
+        return Err(ErrorKind::InvalidChannelCount(sources.len()));
+    }
+    let image = match layout {
+        OutputLayout::Luma8 => {
+            DynamicImage::ImageLuma8(swizzle_buffer(sources, Luma([0]), threads)?)
+        }
+        OutputLayout::LumaA8 => {
+            DynamicImage::ImageLumaA8(swizzle_buffer(sources, LumaA([0, u8::MAX]), threads)?)
+        }
+        OutputLayout::Rgb8 => {
+            DynamicImage::ImageRgb8(swizzle_buffer(sources, Rgb([0; 3]), threads)?)
+        }
+        OutputLayout::Rgba8 => {
+            DynamicImage::ImageRgba8(swizzle_buffer(sources, Rgba([0, 0, 0, u8::MAX]), threads)?)
+        }
+        OutputLayout::Luma16 => {
+            DynamicImage::ImageLuma16(swizzle_buffer(sources, Luma([0]), threads)?)
+        }
+        OutputLayout::LumaA16 => {
+            DynamicImage::ImageLumaA16(swizzle_buffer(sources, LumaA([0, u16::MAX]), threads)?)
+        }
+        OutputLayout::Rgb16 => {
+            DynamicImage::ImageRgb16(swizzle_buffer(sources, Rgb([0; 3]), threads)?)
+        }
+        OutputLayout::Rgba16 => {
+            DynamicImage::ImageRgba16(swizzle_buffer(sources, Rgba([0, 0, 0, u16::MAX]), threads)?)
+        }
+        OutputLayout::Rgb32F => {
+            DynamicImage::ImageRgb32F(swizzle_buffer(sources, Rgb([0.0; 3]), threads)?)
+        }
+        OutputLayout::Rgba32F => DynamicImage::ImageRgba32F(swizzle_buffer(
+            sources,
+            Rgba([0.0, 0.0, 0.0, 1.0]),
+            threads,
+        )?),
+    };
+    Ok(image)
 }
 
-/// Generates a _Grayscale_ image from a single descriptor, and wraps it into
-/// a `image::DynamicImage`
+/// Generates a _Grayscale_ image from a single descriptor
 ///
 /// # Arguments
 ///
 /// * `r` - The descriptor to use for writing the _red_ channel
-pub fn to_luma_dyn(r: &ChannelDescriptor) -> SwizzleResultDyn {
-    Ok(DynamicImage::ImageLuma8(to_luma(r)?))
+pub fn to_luma<S: Into<ChannelSource> + Clone>(r: &S) -> SwizzleResult<image::GrayImage> {
+    swizzle_buffer(&[Some(r.clone().into())], Luma([0]), 1)
 }
 
 /// Generates a _Luminance Alpha_ image from two descriptors
@@ -226,16 +792,14 @@ pub fn to_luma_dyn(r: &ChannelDescriptor) -> SwizzleResultDyn {
 ///
 /// * `r` - The descriptor to use for writing the _red_ channel
 /// * `a` - The descriptor to use for writing the _alpha_ channel
-pub fn to_luma_a(
-    r: &Option<ChannelDescriptor>,
-    a: &Option<ChannelDescriptor>,
+pub fn to_luma_a<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    a: &Option<S>,
 ) -> SwizzleResult<image::GrayAlphaImage> {
-    static DEFAULT: LumaA<u8> = LumaA([0, 255]);
-    swizzle!(DEFAULT, r, a)
+    swizzle_buffer(&to_sources(&[r, a]), LumaA([0, 255]), 1)
 }
 
-/// Generates a _Luminance Alpha_ image from two descriptors, and wraps it
-/// into a `image::DynamicImage`
+/// Generates a _RGB_ image from three descriptors
 ///
 /// **NOTE**: not all descriptors are required. When no descriptor is provided
 /// for a channel, the channel is left empty.
@@ -243,15 +807,17 @@ pub fn to_luma_a(
 /// # Arguments
 ///
 /// * `r` - The descriptor to use for writing the _red_ channel
-/// * `a` - The descriptor to use for writing the _alpha_ channel
-pub fn to_luma_a_dyn(
-    r: &Option<ChannelDescriptor>,
-    g: &Option<ChannelDescriptor>,
-) -> SwizzleResultDyn {
-    Ok(DynamicImage::ImageLumaA8(to_luma_a(r, g)?))
+/// * `g` - The descriptor to use for writing the _green_ channel
+/// * `b` - The descriptor to use for writing the _blue_ channel
+pub fn to_rgb<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+) -> SwizzleResult<image::RgbImage> {
+    swizzle_buffer(&to_sources(&[r, g, b]), Rgb([0, 0, 0]), 1)
 }
 
-/// Generates a _RGB_ image from three descriptors
+/// Generates a _RGBA_ image from four descriptors.
 ///
 /// **NOTE**: not all descriptors are required. When no descriptor is provided
 /// for a channel, the channel is left empty.
@@ -261,17 +827,42 @@ pub fn to_luma_a_dyn(
 /// * `r` - The descriptor to use for writing the _red_ channel
 /// * `g` - The descriptor to use for writing the _green_ channel
 /// * `b` - The descriptor to use for writing the _blue_ channel
-pub fn to_rgb(
-    r: &Option<ChannelDescriptor>,
-    g: &Option<ChannelDescriptor>,
-    b: &Option<ChannelDescriptor>,
-) -> SwizzleResult<image::RgbImage> {
-    static DEFAULT: Rgb<u8> = Rgb([0, 0, 0]);
-    swizzle!(DEFAULT, r, g, b)
+/// * `a` - The descriptor to use for writing the _alpha_ channel
+pub fn to_rgba<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+    a: &Option<S>,
+) -> SwizzleResult<image::RgbaImage> {
+    swizzle_buffer(&to_sources(&[r, g, b, a]), Rgba([0, 0, 0, 255]), 1)
+}
+
+/// Generates a 16 bits _Grayscale_ image from a single descriptor
+///
+/// # Arguments
+///
+/// * `r` - The descriptor to use for writing the _red_ channel
+pub fn to_luma16<S: Into<ChannelSource> + Clone>(r: &S) -> SwizzleResult<Gray16Image> {
+    swizzle_buffer(&[Some(r.clone().into())], Luma([0]), 1)
+}
+
+/// Generates a 16 bits _Luminance Alpha_ image from two descriptors
+///
+/// **NOTE**: not all descriptors are required. When no descriptor is provided
+/// for a channel, the channel is left empty.
+///
+/// # Arguments
+///
+/// * `r` - The descriptor to use for writing the _red_ channel
+/// * `a` - The descriptor to use for writing the _alpha_ channel
+pub fn to_luma_a16<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    a: &Option<S>,
+) -> SwizzleResult<GrayAlpha16Image> {
+    swizzle_buffer(&to_sources(&[r, a]), LumaA([0, u16::MAX]), 1)
 }
 
-/// Generates a _RGB_ image from three descriptors, and wraps it
-/// into a `image::DynamicImage`
+/// Generates a 16 bits _RGB_ image from three descriptors
 ///
 /// **NOTE**: not all descriptors are required. When no descriptor is provided
 /// for a channel, the channel is left empty.
@@ -281,15 +872,15 @@ pub fn to_rgb(
 /// * `r` - The descriptor to use for writing the _red_ channel
 /// * `g` - The descriptor to use for writing the _green_ channel
 /// * `b` - The descriptor to use for writing the _blue_ channel
-pub fn to_rgb_dyn(
-    r: &Option<ChannelDescriptor>,
-    g: &Option<ChannelDescriptor>,
-    b: &Option<ChannelDescriptor>,
-) -> SwizzleResultDyn {
-    Ok(DynamicImage::ImageRgb8(to_rgb(r, g, b)?))
+pub fn to_rgb16<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+) -> SwizzleResult<Rgb16Image> {
+    swizzle_buffer(&to_sources(&[r, g, b]), Rgb([0, 0, 0]), 1)
 }
 
-/// Generates a _RGBA_ image from four descriptors.
+/// Generates a 16 bits _RGBA_ image from four descriptors.
 ///
 /// **NOTE**: not all descriptors are required. When no descriptor is provided
 /// for a channel, the channel is left empty.
@@ -300,18 +891,59 @@ pub fn to_rgb_dyn(
 /// * `g` - The descriptor to use for writing the _green_ channel
 /// * `b` - The descriptor to use for writing the _blue_ channel
 /// * `a` - The descriptor to use for writing the _alpha_ channel
-pub fn to_rgba(
-    r: &Option<ChannelDescriptor>,
-    g: &Option<ChannelDescriptor>,
-    b: &Option<ChannelDescriptor>,
-    a: &Option<ChannelDescriptor>,
-) -> SwizzleResult<image::RgbaImage> {
-    static DEFAULT: Rgba<u8> = Rgba([0, 0, 0, 255]);
-    swizzle!(DEFAULT, r, g, b, a)
+pub fn to_rgba16<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+    a: &Option<S>,
+) -> SwizzleResult<Rgba16Image> {
+    swizzle_buffer(&to_sources(&[r, g, b, a]), Rgba([0, 0, 0, u16::MAX]), 1)
+}
+
+/// Generates a 32 bits float _Grayscale_ image from a single descriptor
+///
+/// # Arguments
+///
+/// * `r` - The descriptor to use for writing the _red_ channel
+pub fn to_luma32f<S: Into<ChannelSource> + Clone>(r: &S) -> SwizzleResult<Gray32FImage> {
+    swizzle_buffer(&[Some(r.clone().into())], Luma([0.0]), 1)
+}
+
+/// Generates a 32 bits float _Luminance Alpha_ image from two descriptors
+///
+/// **NOTE**: not all descriptors are required. When no descriptor is provided
+/// for a channel, the channel is left empty.
+///
+/// # Arguments
+///
+/// * `r` - The descriptor to use for writing the _red_ channel
+/// * `a` - The descriptor to use for writing the _alpha_ channel
+pub fn to_luma_a32f<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    a: &Option<S>,
+) -> SwizzleResult<GrayAlpha32FImage> {
+    swizzle_buffer(&to_sources(&[r, a]), LumaA([0.0, 1.0]), 1)
+}
+
+/// Generates a 32 bits float _RGB_ image from three descriptors
+///
+/// **NOTE**: not all descriptors are required. When no descriptor is provided
+/// for a channel, the channel is left empty.
+///
+/// # Arguments
+///
+/// * `r` - The descriptor to use for writing the _red_ channel
+/// * `g` - The descriptor to use for writing the _green_ channel
+/// * `b` - The descriptor to use for writing the _blue_ channel
+pub fn to_rgb32f<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+) -> SwizzleResult<image::Rgb32FImage> {
+    swizzle_buffer(&to_sources(&[r, g, b]), Rgb([0.0, 0.0, 0.0]), 1)
 }
 
-/// Generates a _RGBA_ image from three descriptors, and wraps it
-/// into a `image::DynamicImage`
+/// Generates a 32 bits float _RGBA_ image from four descriptors.
 ///
 /// **NOTE**: not all descriptors are required. When no descriptor is provided
 /// for a channel, the channel is left empty.
@@ -322,59 +954,296 @@ pub fn to_rgba(
 /// * `g` - The descriptor to use for writing the _green_ channel
 /// * `b` - The descriptor to use for writing the _blue_ channel
 /// * `a` - The descriptor to use for writing the _alpha_ channel
-pub fn to_rgba_dyn(
-    r: &Option<ChannelDescriptor>,
-    g: &Option<ChannelDescriptor>,
-    b: &Option<ChannelDescriptor>,
-    a: &Option<ChannelDescriptor>,
-) -> SwizzleResultDyn {
-    Ok(DynamicImage::ImageRgba8(to_rgba(r, g, b, a)?))
+pub fn to_rgba32f<S: Into<ChannelSource> + Clone>(
+    r: &Option<S>,
+    g: &Option<S>,
+    b: &Option<S>,
+    a: &Option<S>,
+) -> SwizzleResult<image::Rgba32FImage> {
+    swizzle_buffer(&to_sources(&[r, g, b, a]), Rgba([0.0, 0.0, 0.0, 1.0]), 1)
 }
 
-/// Generates a [`image::DynamicImage`] from a vector of descriptors. This
+/// Generates a [`image::DynamicImage`] from a vector of sources. This
 /// method allows to generate an image directly from runtime data.
 ///
 /// # Arguments
 ///
-/// * `descriptors` - Vector of input descriptors. The number of descriptors
-/// will define the shape of the output (Luma, LumaA, RGB, RGBA)
-pub fn to_dynamic(
-    descriptors: &Vec<Option<ChannelDescriptor>>,
+/// * `descriptors` - Vector of input sources. The number of sources will
+///   define the shape of the output (Luma, LumaA, RGB, RGBA)
+pub fn to_dynamic(descriptors: &[Option<ChannelSource>]) -> SwizzleResult<image::DynamicImage> {
+    to_dynamic_with_depth(descriptors, BitDepth::U8)
+}
+
+/// Generates a [`image::DynamicImage`] from a vector of descriptors, using
+/// the given number of bits per channel.
+///
+/// # Arguments
+///
+/// * `descriptors` - Vector of input sources. The number of sources will
+///   define the shape of the output (Luma, LumaA, RGB, RGBA)
+/// * `depth` - Number of bits per channel of the output
+///
+/// Fails with [`ErrorKind::InvalidChannelCount`] if there are no sources, or
+/// more than four sources.
+///
+/// **NOTE**: `image::DynamicImage` has no _Grayscale_ float variants. Use
+/// [`to_luma32f`] and [`to_luma_a32f`] to generate such images.
+pub fn to_dynamic_with_depth(
+    descriptors: &[Option<ChannelSource>],
+    depth: BitDepth,
 ) -> SwizzleResult<image::DynamicImage> {
-    let dynimg = match descriptors.len() {
-        1 => match &descriptors[0] {
-            Some(d) => image::DynamicImage::ImageLuma8(to_luma(d)?),
-            None => return Err(ErrorKind::EmptyDescriptor),
-        },
-        2 => image::DynamicImage::ImageLumaA8(to_luma_a(&descriptors[0], &descriptors[1])?),
-        3 => image::DynamicImage::ImageRgb8(to_rgb(
-            &descriptors[0],
-            &descriptors[1],
-            &descriptors[2],
-        )?),
-        a if a >= 4 => image::DynamicImage::ImageRgba8(to_rgba(
-            &descriptors[0],
-            &descriptors[1],
-            &descriptors[2],
-            &descriptors[3],
-        )?),
-        _ => panic!("too big vector!"),
+    swizzle(descriptors, OutputLayout::new(descriptors.len(), depth)?)
+}
+
+/// Returns the number of threads used by default, i.e the number of logical
+/// cores of the machine.
+pub fn default_threads() -> usize {
+    num_cpus::get()
+}
+
+/// Options used to generate a [`image::DynamicImage`] from runtime data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwizzleOptions {
+    /// Number of bits per channel of the output.
+    pub bit_depth: BitDepth,
+
+    /// Policy used when sources don't share the same dimensions.
+    pub resize: ResizePolicy,
+
+    /// Filter used when sources are resized.
+    pub resize_filter: FilterType,
+
+    /// Number of threads the rows of the image are split between. Use more
+    /// than one thread to swizzle large images faster.
+    pub threads: usize,
+}
+
+impl Default for SwizzleOptions {
+    fn default() -> Self {
+        SwizzleOptions {
+            bit_depth: BitDepth::U8,
+            resize: ResizePolicy::Error,
+            resize_filter: FilterType::Triangle,
+            threads: 1,
+        }
+    }
+}
+
+/// Resizes the images of a list of sources, so that they all share the same
+/// dimensions.
+///
+/// Images used by several sources are only resized once. Sources that
+/// already have the right dimensions are left untouched.
+///
+/// # Arguments
+///
+/// * `sources` - List of sources to resize
+/// * `policy` - Policy choosing the final dimensions
+/// * `filter` - Filter used to sample the resized images
+pub fn resize_sources(
+    sources: &[Option<ChannelSource>],
+    policy: ResizePolicy,
+    filter: FilterType,
+) -> SwizzleResult<Vec<Option<ChannelSource>>> {
+    let dimensions: Vec<(u32, u32)> = sources
+        .iter()
+        .filter_map(|s| match s {
+            Some(ChannelSource::Descriptor(desc)) => Some(desc.img.dimensions()),
+            _ => None,
+        })
+        .collect();
+
+    let target = match policy {
+        ResizePolicy::Error => return Ok(sources.to_vec()),
+        ResizePolicy::Largest => {
+            dimensions
+                .iter()
+                .fold(None, |acc: Option<(u32, u32)>, d| match acc {
+                    Some(a) => Some((a.0.max(d.0), a.1.max(d.1))),
+                    None => Some(*d),
+                })
+        }
+        ResizePolicy::Smallest => {
+            dimensions
+                .iter()
+                .fold(None, |acc: Option<(u32, u32)>, d| match acc {
+                    Some(a) => Some((a.0.min(d.0), a.1.min(d.1))),
+                    None => Some(*d),
+                })
+        }
+        ResizePolicy::Exact(width, height) => Some((width, height)),
     };
-    Ok(dynimg)
+    let (width, height) = match target {
+        Some(d) => d,
+        None => return Ok(sources.to_vec()),
+    };
+    if width == 0 || height == 0 {
+        return Err(ErrorKind::Invalid(format!(
+            "can't resize sources to {}x{}",
+            width, height
+        )));
+    }
+
+    // Keeps track of the images already resized.
+    let mut resized: Vec<(
+        std::sync::Arc<image::DynamicImage>,
+        std::sync::Arc<image::DynamicImage>,
+    )> = Vec::new();
+
+    let result = sources
+        .iter()
+        .map(|source| match source {
+            Some(ChannelSource::Descriptor(desc)) if desc.img.dimensions() != (width, height) => {
+                let img = match resized
+                    .iter()
+                    .find(|(src, _)| std::sync::Arc::ptr_eq(src, &desc.img))
+                {
+                    Some((_, img)) => img.clone(),
+                    None => {
+                        let img = std::sync::Arc::new(desc.img.resize_exact(width, height, filter));
+                        resized.push((desc.img.clone(), img.clone()));
+                        img
+                    }
+                };
+                let mut desc = desc.clone();
+                desc.img = img;
+                Some(ChannelSource::Descriptor(desc))
+            }
+            _ => source.clone(),
+        })
+        .collect();
+    Ok(result)
+}
+
+/// Generates a [`image::DynamicImage`] from a vector of sources, using the
+/// given options.
+///
+/// # Arguments
+///
+/// * `descriptors` - Vector of input sources. The number of sources will
+///   define the shape of the output (Luma, LumaA, RGB, RGBA)
+/// * `options` - Bit depth and resize policy of the output
+pub fn to_dynamic_with_options(
+    descriptors: &[Option<ChannelSource>],
+    options: &SwizzleOptions,
+) -> SwizzleResult<image::DynamicImage> {
+    let sources = resize_sources(descriptors, options.resize, options.resize_filter)?;
+    let layout = OutputLayout::new(sources.len(), options.bit_depth)?;
+    swizzle_layout(&sources, layout, options.threads)
+}
+
+/// Extracts one channel of an image into a grayscale image.
+fn extract_channel<T: Subpixel>(samples: &Samples, channel: u8) -> ImageBuffer<Luma<T>, Vec<T>> {
+    let (width, height) = samples.dimensions();
+    let mut image = ImageBuffer::new(width, height);
+    if width > 0 {
+        for (y, row) in image.chunks_exact_mut(width as usize).enumerate() {
+            samples.swizzle_row(channel, &[], y, row, 1, 0);
+        }
+    }
+    image
+}
+
+/// Splits an image into one grayscale image per channel. This is the inverse
+/// of [`swizzle`].
+///
+/// # Arguments
+///
+/// * `img` - Image to split
+/// * `depth` - Bits per channel of the generated images. Grayscale images
+///   can't be stored as floats, so only `U8` and `U16` are supported
+///
+/// # Examples
+///
+/// ```no_run
+/// use swizzler::{unswizzle, BitDepth};
+///
+/// let orm = image::open("./orm.png").unwrap();
+/// let channels = unswizzle(&orm, BitDepth::U8).unwrap();
+/// ```
+pub fn unswizzle(img: &DynamicImage, depth: BitDepth) -> SwizzleResult<Vec<DynamicImage>> {
+    let names: Vec<Option<&str>> = vec![Some(""); img.color().channel_count() as usize];
+    let images = unswizzle_named(img, &names, depth)?;
+    Ok(images.into_iter().map(|(_, image)| image).collect())
+}
+
+/// Splits an image into named grayscale images, one per channel.
+///
+/// Names are given in channel order. Channels with a `None` name, or past the
+/// last name, aren't extracted.
+///
+/// # Arguments
+///
+/// * `img` - Image to split
+/// * `names` - Name of each channel
+/// * `depth` - Bits per channel of the generated images, `U8` or `U16`
+///
+/// # Examples
+///
+/// ```no_run
+/// use swizzler::{unswizzle_named, BitDepth};
+///
+/// let orm = image::open("./orm.png").unwrap();
+/// let names = [Some("ao"), Some("roughness"), Some("metallic")];
+/// for (name, image) in unswizzle_named(&orm, &names, BitDepth::U8).unwrap() {
+///     image.save(format!("./{}.png", name)).unwrap();
+/// }
+/// ```
+pub fn unswizzle_named<S: AsRef<str>>(
+    img: &DynamicImage,
+    names: &[Option<S>],
+    depth: BitDepth,
+) -> SwizzleResult<Vec<(String, DynamicImage)>> {
+    let layout = OutputLayout::new(1, depth)?;
+    let channels = img.color().channel_count();
+    // Reports the first channel the image doesn't have, as the number of
+    // names may not fit in a channel index.
+    if names.len() > channels as usize {
+        return Err(ErrorKind::InvalidChannel {
+            channel: channels,
+            channels,
+        });
+    }
+
+    let img = Samples::readable(img);
+    let samples = Samples::new(&img);
+    let images = names
+        .iter()
+        .enumerate()
+        .filter_map(|(channel, name)| Some((channel as u8, name.as_ref()?)))
+        .map(|(channel, name)| {
+            let image = match layout {
+                OutputLayout::Luma16 => {
+                    DynamicImage::ImageLuma16(extract_channel(&samples, channel))
+                }
+                _ => DynamicImage::ImageLuma8(extract_channel(&samples, channel)),
+            };
+            (String::from(name.as_ref()), image)
+        })
+        .collect();
+    Ok(images)
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::swizzle::{to_luma, to_luma_a, to_rgb, to_rgba, ChannelDescriptor};
+    use crate::errors::ErrorKind;
+    use crate::swizzle::{
+        read_row, resize_sources, swizzle, to_dynamic, to_dynamic_with_options, to_luma, to_luma_a,
+        to_luma_a16, to_rgb, to_rgba, to_rgba16, to_rgba32f, unswizzle, unswizzle_named, BitDepth,
+        ChannelDescriptor, ChannelSource, OutputLayout, ResizePolicy, Rgba16Image, SwizzleOptions,
+        Transform,
+    };
+    use image::flat::{FlatSamples, SampleLayout};
+    use image::imageops::FilterType;
     use image::{
-        DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage, Rgba,
-        RgbaImage,
+        DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Luma, LumaA, Rgb, Rgb32FImage,
+        RgbImage, Rgba, RgbaImage,
     };
 
-    fn assert_pixels<P: image::Pixel, Container>(img: &ImageBuffer<P, Container>, expected: &[P])
+    fn assert_pixels<P, Container>(img: &ImageBuffer<P, Container>, expected: &[P])
     where
-        P: std::cmp::PartialEq + std::fmt::Debug + 'static,
+        P: image::Pixel + std::cmp::PartialEq + std::fmt::Debug + 'static,
         Container: std::ops::Deref<Target = [P::Subpixel]>,
     {
         let (width, _) = img.dimensions();
@@ -398,21 +1267,21 @@ mod tests {
         img.put_pixel(1, 0, Rgb([0, 135, 97]));
         img.put_pixel(0, 1, Rgb([255, 78, 23]));
         img.put_pixel(1, 1, Rgb([100, 0, 255]));
-        let img = std::rc::Rc::new(DynamicImage::ImageRgb8(img));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgb8(img));
 
         // Test with a `red` descriptor
-        let descriptor = ChannelDescriptor::from_image_rc(&img, 0).unwrap();
+        let descriptor = ChannelDescriptor::from_image_arc(&img, 0).unwrap();
         let result = to_luma(&descriptor).unwrap();
         assert_eq!(result.dimensions(), (2, 2));
         assert_pixels(&result, &[Luma([128]), Luma([0]), Luma([255]), Luma([100])]);
 
         // Test with a `green` descriptor
-        let descriptor = ChannelDescriptor::from_image_rc(&img, 1).unwrap();
+        let descriptor = ChannelDescriptor::from_image_arc(&img, 1).unwrap();
         let result = to_luma(&descriptor).unwrap();
         assert_pixels(&result, &[Luma([128]), Luma([135]), Luma([78]), Luma([0])]);
 
         // Test with a `blue` descriptor
-        let descriptor = ChannelDescriptor::from_image_rc(&img, 2).unwrap();
+        let descriptor = ChannelDescriptor::from_image_arc(&img, 2).unwrap();
         let result = to_luma(&descriptor).unwrap();
         assert_pixels(&result, &[Luma([128]), Luma([97]), Luma([23]), Luma([255])]);
     }
@@ -422,16 +1291,16 @@ mod tests {
         let mut img: GrayAlphaImage = ImageBuffer::new(2, 1);
         img.put_pixel(0, 0, LumaA([0, 250]));
         img.put_pixel(1, 0, LumaA([129, 13]));
-        let img = std::rc::Rc::new(DynamicImage::ImageLumaA8(img));
+        let img = std::sync::Arc::new(DynamicImage::ImageLumaA8(img));
 
         // Test with a `red` descriptor
-        let descriptor_r = Some(ChannelDescriptor::from_image_rc(&img, 1).unwrap());
+        let descriptor_r = Some(ChannelDescriptor::from_image_arc(&img, 1).unwrap());
         let result = to_luma_a(&descriptor_r, &None).unwrap();
         assert_eq!(result.dimensions(), (2, 1));
         assert_pixels(&result, &[LumaA([250, 255]), LumaA([13, 255])]);
 
         // Test with a `red` + `alpha` descriptors
-        let descriptor_a = Some(ChannelDescriptor::from_image_rc(&img, 0).unwrap());
+        let descriptor_a = Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap());
         let result = to_luma_a(&descriptor_r, &descriptor_a).unwrap();
         assert_pixels(&result, &[LumaA([250, 0]), LumaA([13, 129])]);
     }
@@ -441,12 +1310,12 @@ mod tests {
         let mut img: RgbaImage = ImageBuffer::new(2, 1);
         img.put_pixel(0, 0, Rgba([1, 2, 3, 255]));
         img.put_pixel(1, 0, Rgba([127, 128, 126, 0]));
-        let img = std::rc::Rc::new(DynamicImage::ImageRgba8(img));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgba8(img));
 
         // Test to set only the `green` channel
         let result = to_rgb(
             &None,
-            &Some(ChannelDescriptor::from_image_rc(&img, 3).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 3).unwrap()),
             &None,
         )
         .unwrap();
@@ -455,9 +1324,9 @@ mod tests {
 
         // Test to set all channels
         let result = to_rgb(
-            &Some(ChannelDescriptor::from_image_rc(&img, 2).unwrap()),
-            &Some(ChannelDescriptor::from_image_rc(&img, 1).unwrap()),
-            &Some(ChannelDescriptor::from_image_rc(&img, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 2).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 1).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap()),
         )
         .unwrap();
         assert_pixels(&result, &[Rgb([3, 2, 1]), Rgb([126, 128, 127])]);
@@ -468,14 +1337,14 @@ mod tests {
         let mut img: RgbaImage = ImageBuffer::new(2, 1);
         img.put_pixel(0, 0, Rgba([255, 128, 0, 0]));
         img.put_pixel(1, 0, Rgba([0, 128, 255, 255]));
-        let img = std::rc::Rc::new(DynamicImage::ImageRgba8(img));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgba8(img));
 
         // Test to set the `red` and `alpha` channels
         let result = to_rgba(
-            &Some(ChannelDescriptor::from_image_rc(&img, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap()),
             &None,
             &None,
-            &Some(ChannelDescriptor::from_image_rc(&img, 3).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 3).unwrap()),
         )
         .unwrap();
         assert_eq!(result.dimensions(), (2, 1));
@@ -483,10 +1352,10 @@ mod tests {
 
         // Test to set all channels
         let result = to_rgba(
-            &Some(ChannelDescriptor::from_image_rc(&img, 3).unwrap()),
-            &Some(ChannelDescriptor::from_image_rc(&img, 2).unwrap()),
-            &Some(ChannelDescriptor::from_image_rc(&img, 1).unwrap()),
-            &Some(ChannelDescriptor::from_image_rc(&img, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 3).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 2).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 1).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap()),
         )
         .unwrap();
         assert_pixels(&result, &[Rgba([0, 0, 128, 255]), Rgba([255, 255, 128, 0])]);
@@ -536,15 +1405,428 @@ mod tests {
         let img_2 = DynamicImage::ImageRgb8(img_2);
 
         let result = to_rgba(
-            &Some(ChannelDescriptor::from_image(img_1, 1).unwrap()),
-            &Some(ChannelDescriptor::from_image(img_2, 3).unwrap()),
+            &Some(ChannelDescriptor::from_image(img_1, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image(img_2, 2).unwrap()),
             &None,
             &None,
         );
 
         assert!(
-            result.is_err(),
+            matches!(
+                result,
+                Err(ErrorKind::InvalidSize {
+                    expected: (2, 1),
+                    actual: (1, 1)
+                })
+            ),
             "should be an error because of invalid dimensions"
         );
     }
+
+    #[test]
+    fn swizzle_rgba16() {
+        let mut img: Rgba16Image = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Rgba([1, 257, 40000, 65535]));
+        img.put_pixel(1, 0, Rgba([65534, 0, 12345, 2]));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgba16(img));
+
+        // Test that 16 bits sources aren't truncated
+        let result = to_rgba16(
+            &Some(ChannelDescriptor::from_image_arc(&img, 3).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 2).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img, 1).unwrap()),
+            &None,
+        )
+        .unwrap();
+        assert_eq!(result.dimensions(), (2, 1));
+        assert_pixels(
+            &result,
+            &[Rgba([65535, 40000, 257, 65535]), Rgba([2, 12345, 0, 65535])],
+        );
+    }
+
+    #[test]
+    fn swizzle_mixed_depths() {
+        let mut img_8: GrayImage = ImageBuffer::new(2, 1);
+        img_8.put_pixel(0, 0, Luma([255]));
+        img_8.put_pixel(1, 0, Luma([171]));
+        let img_8 = std::sync::Arc::new(DynamicImage::ImageLuma8(img_8));
+
+        let mut img_16: Rgba16Image = ImageBuffer::new(2, 1);
+        img_16.put_pixel(0, 0, Rgba([128, 0, 0, 0]));
+        img_16.put_pixel(1, 0, Rgba([65535, 0, 0, 0]));
+        let img_16 = std::sync::Arc::new(DynamicImage::ImageRgba16(img_16));
+
+        // Test that 8 bits sources are widened
+        let result = to_luma_a16(
+            &Some(ChannelDescriptor::from_image_arc(&img_8, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_16, 0).unwrap()),
+        )
+        .unwrap();
+        assert_pixels(&result, &[LumaA([65535, 128]), LumaA([43947, 65535])]);
+
+        // Test that 16 bits sources are narrowed
+        let result = to_luma_a(
+            &Some(ChannelDescriptor::from_image_arc(&img_16, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_8, 0).unwrap()),
+        )
+        .unwrap();
+        assert_pixels(&result, &[LumaA([0, 255]), LumaA([255, 171])]);
+    }
+
+    #[test]
+    fn swizzle_float() {
+        let mut img_hdr: Rgb32FImage = ImageBuffer::new(2, 1);
+        img_hdr.put_pixel(0, 0, Rgb([0.25, 4.5, -1.0]));
+        img_hdr.put_pixel(1, 0, Rgb([1.0, 0.5, 0.0]));
+        let img_hdr = std::sync::Arc::new(DynamicImage::ImageRgb32F(img_hdr));
+
+        let mut img_8: GrayImage = ImageBuffer::new(2, 1);
+        img_8.put_pixel(0, 0, Luma([255]));
+        img_8.put_pixel(1, 0, Luma([51]));
+        let img_8 = std::sync::Arc::new(DynamicImage::ImageLuma8(img_8));
+
+        // Test that float sources are kept as is, and integer sources are
+        // normalized
+        let result = to_rgba32f(
+            &Some(ChannelDescriptor::from_image_arc(&img_hdr, 1).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_hdr, 2).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_8, 0).unwrap()),
+            &None,
+        )
+        .unwrap();
+        assert_pixels(
+            &result,
+            &[Rgba([4.5, -1.0, 1.0, 1.0]), Rgba([0.5, 0.0, 0.2, 1.0])],
+        );
+
+        // Test that float sources are clamped and quantized
+        let result = to_rgb(
+            &Some(ChannelDescriptor::from_image_arc(&img_hdr, 0).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_hdr, 1).unwrap()),
+            &Some(ChannelDescriptor::from_image_arc(&img_hdr, 2).unwrap()),
+        )
+        .unwrap();
+        assert_pixels(&result, &[Rgb([64, 255, 0]), Rgb([255, 128, 0])]);
+    }
+
+    #[test]
+    fn use_invalid_channels() {
+        let img: RgbImage = ImageBuffer::new(1, 1);
+        let img = std::sync::Arc::new(DynamicImage::ImageRgb8(img));
+
+        assert!(matches!(
+            ChannelDescriptor::from_image_arc(&img, 3),
+            Err(ErrorKind::InvalidChannel {
+                channel: 3,
+                channels: 3
+            })
+        ));
+        let names: Vec<Option<&str>> = vec![Some("x"); 300];
+        assert!(matches!(
+            unswizzle_named(&img, &names, BitDepth::U8),
+            Err(ErrorKind::InvalidChannel {
+                channel: 3,
+                channels: 3
+            })
+        ));
+        let names: [Option<&str>; 0] = [];
+        assert!(unswizzle_named(&img, &names, BitDepth::U8)
+            .unwrap()
+            .is_empty());
+
+        // Channel changed after the descriptor creation.
+        let mut descriptor = ChannelDescriptor::from_image_arc(&img, 0).unwrap();
+        descriptor.channel = 4;
+        assert!(matches!(
+            to_luma(&descriptor),
+            Err(ErrorKind::InvalidChannel {
+                channel: 4,
+                channels: 3
+            })
+        ));
+
+        let source: ChannelSource = ChannelDescriptor::from_image_arc(&img, 0).unwrap().into();
+        assert!(matches!(
+            to_dynamic(&[]),
+            Err(ErrorKind::InvalidChannelCount(0))
+        ));
+        assert!(matches!(
+            to_dynamic(&vec![Some(source); 5]),
+            Err(ErrorKind::InvalidChannelCount(5))
+        ));
+    }
+
+    #[test]
+    fn swizzle_layouts() {
+        let mut img: RgbImage = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Rgb([10, 20, 30]));
+        img.put_pixel(1, 0, Rgb([40, 50, 60]));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgb8(img));
+        let source = |channel: u8| -> Option<ChannelSource> {
+            Some(
+                ChannelDescriptor::from_image_arc(&img, channel)
+                    .unwrap()
+                    .into(),
+            )
+        };
+
+        // Missing channels are left empty, with an opaque alpha.
+        let result = swizzle(&[source(2), None, source(0)], OutputLayout::Rgba8).unwrap();
+        assert_pixels(
+            result.as_rgba8().unwrap(),
+            &[Rgba([30, 0, 10, 255]), Rgba([60, 0, 40, 255])],
+        );
+
+        let result = swizzle(&[source(1)], OutputLayout::LumaA16).unwrap();
+        assert_pixels(
+            result.as_luma_alpha16().unwrap(),
+            &[LumaA([20 * 257, u16::MAX]), LumaA([50 * 257, u16::MAX])],
+        );
+
+        assert!(matches!(
+            swizzle(&[source(0), source(1)], OutputLayout::Luma8),
+            Err(ErrorKind::InvalidChannelCount(2))
+        ));
+        assert!(matches!(
+            swizzle(&[None, None], OutputLayout::Rgb8),
+            Err(ErrorKind::EmptyDescriptor)
+        ));
+
+        assert_eq!(
+            OutputLayout::new(3, BitDepth::U16).unwrap(),
+            OutputLayout::Rgb16
+        );
+        assert_eq!(OutputLayout::Rgba32F.channel_count(), 4);
+        assert_eq!(OutputLayout::Rgba32F.bit_depth(), BitDepth::F32);
+        assert!(OutputLayout::new(1, BitDepth::F32).is_err());
+        assert!(OutputLayout::new(5, BitDepth::U8).is_err());
+    }
+
+    #[test]
+    fn swizzle_threads() {
+        let img: RgbImage = ImageBuffer::from_fn(7, 13, |x, y| {
+            Rgb([(x * 30) as u8, (y * 19) as u8, (x * y) as u8])
+        });
+        let img = std::sync::Arc::new(DynamicImage::ImageRgb8(img));
+        let mut inverted = ChannelDescriptor::from_image_arc(&img, 1).unwrap();
+        inverted.transforms.push(Transform::Invert);
+        let sources = [
+            Some(ChannelDescriptor::from_image_arc(&img, 2).unwrap().into()),
+            Some(inverted.into()),
+            Some(ChannelSource::Constant(0.5)),
+            Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap().into()),
+        ];
+
+        let single = to_dynamic_with_options(&sources, &SwizzleOptions::default()).unwrap();
+        let pixel = single.as_rgba8().unwrap().get_pixel(3, 5);
+        assert_eq!(*pixel, Rgba([15, 255 - 95, 128, 90]));
+
+        // Includes more threads than rows.
+        for threads in &[2, 4, 13, 64] {
+            let options = SwizzleOptions {
+                threads: *threads,
+                ..SwizzleOptions::default()
+            };
+            let result = to_dynamic_with_options(&sources, &options).unwrap();
+            assert_eq!(result.as_bytes(), single.as_bytes());
+        }
+    }
+
+    #[test]
+    fn read_strided_rows() {
+        // Planar 2x2 image, with all red values stored before green values.
+        let flat = FlatSamples {
+            samples: &[1u8, 2, 3, 4, 5, 6, 7, 8][..],
+            layout: SampleLayout {
+                channels: 2,
+                channel_stride: 4,
+                width: 2,
+                width_stride: 1,
+                height: 2,
+                height_stride: 2,
+            },
+            color_hint: None,
+        };
+        let mut row = [0u16; 6];
+        read_row(&flat, 1, &[], 1, &mut row, 3, 2);
+        assert_eq!(row, [0, 0, 7 * 257, 0, 0, 8 * 257]);
+    }
+
+    #[test]
+    fn unswizzle_channels() {
+        let mut img: RgbImage = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Rgb([10, 20, 30]));
+        img.put_pixel(1, 0, Rgb([40, 50, 60]));
+        let img = DynamicImage::ImageRgb8(img);
+
+        let channels = unswizzle(&img, BitDepth::U8).unwrap();
+        assert_eq!(channels.len(), 3);
+        assert_pixels(channels[0].as_luma8().unwrap(), &[Luma([10]), Luma([40])]);
+        assert_pixels(channels[2].as_luma8().unwrap(), &[Luma([30]), Luma([60])]);
+
+        let names = [Some("ao"), None, Some("metallic")];
+        let channels = unswizzle_named(&img, &names, BitDepth::U16).unwrap();
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[1].0, "metallic");
+        assert_pixels(
+            channels[1].1.as_luma16().unwrap(),
+            &[Luma([30 * 257]), Luma([60 * 257])],
+        );
+
+        // Swizzling the channels back gives the original image.
+        let sources: Vec<Option<ChannelSource>> = unswizzle(&img, BitDepth::U8)
+            .unwrap()
+            .into_iter()
+            .map(|c| Some(ChannelDescriptor::from_image(c, 0).unwrap().into()))
+            .collect();
+        assert_eq!(to_dynamic(&sources).unwrap().as_bytes(), img.as_bytes());
+
+        assert!(matches!(
+            unswizzle_named(&img, &[Some("a"), None, None, Some("d")], BitDepth::U8),
+            Err(ErrorKind::InvalidChannel {
+                channel: 3,
+                channels: 3
+            })
+        ));
+        assert!(matches!(
+            unswizzle(&img, BitDepth::F32),
+            Err(ErrorKind::UnsupportedLayout(_))
+        ));
+    }
+
+    #[test]
+    fn swizzle_constants() {
+        let mut img: GrayImage = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Luma([12]));
+        img.put_pixel(1, 0, Luma([34]));
+        let img = std::sync::Arc::new(DynamicImage::ImageLuma8(img));
+
+        let result = to_rgba(
+            &Some(ChannelSource::Constant(0.0)),
+            &Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap().into()),
+            &Some(ChannelSource::from_description("0.5").unwrap()),
+            &Some(ChannelSource::Constant(1.0)),
+        )
+        .unwrap();
+        assert_pixels(&result, &[Rgba([0, 12, 128, 255]), Rgba([0, 34, 128, 255])]);
+
+        for invalid in &["-3", "7", "1.5", "nan", "inf", "-inf"] {
+            assert!(matches!(
+                ChannelSource::from_description(invalid),
+                Err(ErrorKind::InvalidDescriptorString(_))
+            ));
+        }
+
+        // Test that constants override the default alpha value
+        let result = to_dynamic(&[
+            Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap().into()),
+            Some(ChannelSource::Constant(0.0)),
+        ])
+        .unwrap();
+        assert_pixels(
+            result.as_luma_alpha8().unwrap(),
+            &[LumaA([12, 0]), LumaA([34, 0])],
+        );
+    }
+
+    #[test]
+    fn swizzle_transforms() {
+        let mut img: GrayImage = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Luma([0]));
+        img.put_pixel(1, 0, Luma([204]));
+        let img = std::sync::Arc::new(DynamicImage::ImageLuma8(img));
+
+        let result = to_rgb(
+            &Some(
+                ChannelDescriptor::from_image_arc(&img, 0)
+                    .unwrap()
+                    .add_transform(Transform::Invert),
+            ),
+            &Some(
+                ChannelDescriptor::from_image_arc(&img, 0)
+                    .unwrap()
+                    .add_transform(Transform::Scale(0.5))
+                    .add_transform(Transform::Bias(0.5)),
+            ),
+            &Some(ChannelDescriptor::from_image_arc(&img, 0).unwrap()),
+        )
+        .unwrap();
+        assert_pixels(&result, &[Rgb([255, 128, 0]), Rgb([51, 230, 204])]);
+    }
+
+    #[test]
+    fn resize_non_matching_dimensions() {
+        let mut img_1: GrayImage = ImageBuffer::new(2, 2);
+        img_1.put_pixel(0, 0, Luma([1]));
+        img_1.put_pixel(1, 0, Luma([2]));
+        img_1.put_pixel(0, 1, Luma([3]));
+        img_1.put_pixel(1, 1, Luma([4]));
+        let img_1 = std::sync::Arc::new(DynamicImage::ImageLuma8(img_1));
+
+        let mut img_2: GrayImage = ImageBuffer::new(1, 1);
+        img_2.put_pixel(0, 0, Luma([200]));
+        let img_2 = std::sync::Arc::new(DynamicImage::ImageLuma8(img_2));
+
+        let sources = vec![
+            Some(ChannelDescriptor::from_image_arc(&img_1, 0).unwrap().into()),
+            Some(ChannelDescriptor::from_image_arc(&img_2, 0).unwrap().into()),
+        ];
+
+        // Test that the default policy doesn't resize
+        let options = SwizzleOptions::default();
+        assert!(to_dynamic_with_options(&sources, &options).is_err());
+
+        // Test upscaling to the largest source
+        let options = SwizzleOptions {
+            resize: ResizePolicy::Largest,
+            resize_filter: FilterType::Nearest,
+            ..SwizzleOptions::default()
+        };
+        let result = to_dynamic_with_options(&sources, &options).unwrap();
+        assert_pixels(
+            result.as_luma_alpha8().unwrap(),
+            &[
+                LumaA([1, 200]),
+                LumaA([2, 200]),
+                LumaA([3, 200]),
+                LumaA([4, 200]),
+            ],
+        );
+
+        // Test downscaling to the smallest source
+        let resized = resize_sources(&sources, ResizePolicy::Smallest, FilterType::Nearest);
+        let result = to_luma_a(&resized.unwrap()[0], &None).unwrap();
+        assert_eq!(result.dimensions(), (1, 1));
+
+        // Test resizing to an explicit size
+        let resized = resize_sources(&sources, ResizePolicy::Exact(3, 1), FilterType::Triangle);
+        let resized = resized.unwrap();
+        let result = to_luma_a(&resized[0], &resized[1]).unwrap();
+        assert_eq!(result.dimensions(), (3, 1));
+    }
+
+    #[test]
+    fn swizzle_from_threads() {
+        let mut img: RgbImage = ImageBuffer::new(2, 1);
+        img.put_pixel(0, 0, Rgb([1, 2, 3]));
+        img.put_pixel(1, 0, Rgb([4, 5, 6]));
+        let img = std::sync::Arc::new(DynamicImage::ImageRgb8(img));
+
+        // Descriptors sharing the same image are sent to different threads.
+        let results: Vec<GrayImage> = crossbeam::scope(|scope| {
+            let handles: Vec<_> = (0..3)
+                .map(|channel| {
+                    let descriptor = ChannelDescriptor::from_image_arc(&img, channel).unwrap();
+                    scope.spawn(move |_| to_luma(&descriptor).unwrap())
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        })
+        .unwrap();
+
+        assert_pixels(&results[0], &[Luma([1]), Luma([4])]);
+        assert_pixels(&results[1], &[Luma([2]), Luma([5])]);
+        assert_pixels(&results[2], &[Luma([3]), Luma([6])]);
+    }
 }
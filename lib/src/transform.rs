@@ -0,0 +1,226 @@
+use crate::errors::ErrorKind;
+
+/// Operation applied on the values read from a source channel.
+///
+/// Transforms work on values normalized in the range `[0, 1]`, i.e `255` for
+/// a 8 bits image is read as `1.0`. The result is converted back to the
+/// output subpixel type once all transforms are applied.
+///
+/// # Examples
+///
+/// Transforms can be parsed from a string:
+///
+/// ```
+/// use swizzler::Transform;
+///
+/// let invert: Transform = "invert".parse().unwrap();
+/// let gamma: Transform = "gamma=2.2".parse().unwrap();
+/// let remap: Transform = "remap=0.2,0.8,0,1".parse().unwrap();
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transform {
+    /// Inverts the value, i.e `1 - x`. Use this to convert a roughness map
+    /// into a gloss map for instance.
+    Invert,
+    /// Raises the value to the given power.
+    Gamma(f32),
+    /// Converts a value encoded in sRGB into a linear value.
+    SrgbToLinear,
+    /// Converts a linear value into a value encoded in sRGB.
+    LinearToSrgb,
+    /// Multiplies the value by the given factor.
+    Scale(f32),
+    /// Adds the given offset to the value.
+    Bias(f32),
+    /// Linearly remaps the range `[from_min, from_max]` into the range
+    /// `[to_min, to_max]`. An empty source range acts as a threshold, values
+    /// below `from_min` becoming `to_min` and others `to_max`.
+    Remap {
+        from_min: f32,
+        from_max: f32,
+        to_min: f32,
+        to_max: f32,
+    },
+}
+
+impl Transform {
+    /// Applies the transform on a normalized value.
+    pub fn apply(&self, value: f32) -> f32 {
+        match *self {
+            Transform::Invert => 1.0 - value,
+            Transform::Gamma(gamma) => value.powf(gamma),
+            Transform::SrgbToLinear => {
+                if value <= 0.04045 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            Transform::LinearToSrgb => {
+                if value <= 0.003_130_8 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            Transform::Scale(scale) => value * scale,
+            Transform::Bias(bias) => value + bias,
+            Transform::Remap {
+                from_min,
+                from_max,
+                to_min,
+                to_max,
+            } => {
+                if from_min == from_max {
+                    return if value < from_min { to_min } else { to_max };
+                }
+                let t = (value - from_min) / (from_max - from_min);
+                to_min + t * (to_max - to_min)
+            }
+        }
+    }
+}
+
+/// Applies a list of transforms, in order, on a normalized value.
+pub(crate) fn apply_all(transforms: &[Transform], value: f32) -> f32 {
+    transforms.iter().fold(value, |v, t| t.apply(v))
+}
+
+impl std::str::FromStr for Transform {
+    type Err = ErrorKind;
+
+    /// Parses a transform from a string with the format `name` or
+    /// `name=arg0,arg1,...`.
+    ///
+    /// Available transforms are:
+    /// * `invert`
+    /// * `gamma=<gamma>`
+    /// * `srgb_to_linear`
+    /// * `linear_to_srgb`
+    /// * `scale=<factor>`
+    /// * `bias=<offset>`
+    /// * `remap=<from_min>,<from_max>,<to_min>,<to_max>`
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || ErrorKind::InvalidTransform(String::from(input));
+
+        let mut split = input.trim().splitn(2, '=');
+        let name = split.next().ok_or_else(invalid)?.to_lowercase();
+        let args = match split.next() {
+            Some(args) => args
+                .split(',')
+                .map(|a| a.trim().parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| invalid())?,
+            None => Vec::new(),
+        };
+
+        match (name.as_str(), args.as_slice()) {
+            ("invert", []) => Ok(Transform::Invert),
+            ("gamma", [gamma]) => Ok(Transform::Gamma(*gamma)),
+            ("srgb_to_linear", []) => Ok(Transform::SrgbToLinear),
+            ("linear_to_srgb", []) => Ok(Transform::LinearToSrgb),
+            ("scale", [scale]) => Ok(Transform::Scale(*scale)),
+            ("bias", [bias]) => Ok(Transform::Bias(*bias)),
+            ("remap", [from_min, from_max, to_min, to_max]) => Ok(Transform::Remap {
+                from_min: *from_min,
+                from_max: *from_max,
+                to_min: *to_min,
+                to_max: *to_max,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl std::fmt::Display for Transform {
+    /// Formats the transform with the format parsed by `from_str`.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Transform::Invert => write!(f, "invert"),
+            Transform::Gamma(gamma) => write!(f, "gamma={}", gamma),
+            Transform::SrgbToLinear => write!(f, "srgb_to_linear"),
+            Transform::LinearToSrgb => write!(f, "linear_to_srgb"),
+            Transform::Scale(scale) => write!(f, "scale={}", scale),
+            Transform::Bias(bias) => write!(f, "bias={}", bias),
+            Transform::Remap {
+                from_min,
+                from_max,
+                to_min,
+                to_max,
+            } => write!(f, "remap={},{},{},{}", from_min, from_max, to_min, to_max),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::transform::Transform;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 1e-4,
+            "expected `{}`, got `{}`",
+            expected,
+            value
+        );
+    }
+
+    #[test]
+    fn parse_transforms() {
+        assert_eq!("invert".parse::<Transform>().unwrap(), Transform::Invert);
+        assert_eq!(
+            "gamma=2.2".parse::<Transform>().unwrap(),
+            Transform::Gamma(2.2)
+        );
+        assert_eq!(
+            "remap=0.2, 0.8, 0, 1".parse::<Transform>().unwrap(),
+            Transform::Remap {
+                from_min: 0.2,
+                from_max: 0.8,
+                to_min: 0.0,
+                to_max: 1.0
+            }
+        );
+        let remap = "remap=0.2,0.8,0,1".parse::<Transform>().unwrap();
+        assert_eq!(remap.to_string(), "remap=0.2,0.8,0,1");
+        assert_eq!(remap.to_string().parse::<Transform>().unwrap(), remap);
+
+        // An empty source range is a threshold.
+        let threshold = "remap=0.5,0.5,0,1".parse::<Transform>().unwrap();
+        assert_eq!(threshold.to_string(), "remap=0.5,0.5,0,1");
+        assert_eq!(
+            threshold.to_string().parse::<Transform>().unwrap(),
+            threshold
+        );
+        assert!("gamma".parse::<Transform>().is_err());
+        assert!("invert=1".parse::<Transform>().is_err());
+        assert!("unknown".parse::<Transform>().is_err());
+    }
+
+    #[test]
+    fn apply_transforms() {
+        assert_close(Transform::Invert.apply(0.25), 0.75);
+        assert_close(Transform::Gamma(2.0).apply(0.5), 0.25);
+        assert_close(Transform::SrgbToLinear.apply(0.5), 0.214_041);
+        assert_close(Transform::LinearToSrgb.apply(0.214_041), 0.5);
+        assert_close(Transform::Scale(2.0).apply(0.25), 0.5);
+        assert_close(Transform::Bias(-0.25).apply(0.5), 0.25);
+        let remap = Transform::Remap {
+            from_min: 0.2,
+            from_max: 0.6,
+            to_min: 0.0,
+            to_max: 1.0,
+        };
+        assert_close(remap.apply(0.4), 0.5);
+
+        let threshold = Transform::Remap {
+            from_min: 0.5,
+            from_max: 0.5,
+            to_min: 0.0,
+            to_max: 1.0,
+        };
+        assert_close(threshold.apply(0.4), 0.0);
+        assert_close(threshold.apply(0.5), 1.0);
+    }
+}
@@ -1,20 +1,107 @@
 use image::{open, Rgba};
 use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use swizzler::errors::ErrorKind;
 use swizzler::session::{
-    resolve_assets_dir, GenericAssetReader, GenericTarget, RegexMatcher, Session,
+    resolve_assets_dir, resolve_assets_dir_with_options, resolve_manifest, Asset,
+    CancellationToken, FolderAssetReader, GenericAsset, GenericAssetReader, GenericTarget,
+    GroupingScope, ImageCache, ManifestAsset, ManifestAssetReader, MatchPolicy, MissingInputPolicy,
+    MissingInputs, RegexMatcher, ResolveDiagnostic, ResolveOptions, RunStatus, Session,
+    SessionEvent, SymlinkPolicy, Target, TargetInput, MANIFEST_FILENAME,
 };
+use swizzler::{BitDepth, Transform};
+
+/// Folder of the textures used by the tests.
+const TEXTURES: &str = "./tests/textures";
+
+/// Creates a reader grouping files by the part of their name before the last
+/// `_`, with one matcher per identifier, matching names containing it
+/// regardless of case.
+fn reader(ids: &[&'static str]) -> GenericAssetReader<&'static str> {
+    ids.iter().fold(
+        GenericAssetReader::new().set_base(Regex::new(r"(.*)_.*").unwrap()),
+        |reader, id| {
+            reader.add_matcher(Box::new(RegexMatcher::new(
+                *id,
+                Regex::new(&format!("(?i){}", id)).unwrap(),
+            )))
+        },
+    )
+}
+
+/// Creates the input folder `name` from scratch, copying each texture of
+/// `tests/textures` to its path relative to the folder.
+fn fixture_folder<P: AsRef<Path>>(name: &str, files: &[(&str, P)]) -> PathBuf {
+    let folder = PathBuf::from("./_tests_output_/inputs").join(name);
+    std::fs::remove_dir_all(&folder).ok();
+    std::fs::create_dir_all(&folder).unwrap();
+    for (texture, path) in files {
+        let path = folder.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::copy(Path::new(TEXTURES).join(texture), path).unwrap();
+    }
+    folder
+}
+
+/// Files of `count` copies of the asset `a`, named `0`, `1`, etc.
+fn asset_copies(count: usize) -> Vec<(&'static str, String)> {
+    (0..count)
+        .flat_map(|i| {
+            vec![
+                ("a_albedo.png", format!("{}_albedo.png", i)),
+                ("a_ao.png", format!("{}_ao.png", i)),
+            ]
+        })
+        .collect()
+}
+
+/// Returns the output folder `name`, without the outputs of previous runs.
+fn output_folder(name: &str) -> PathBuf {
+    let folder = PathBuf::from("./_tests_output_").join(name);
+    std::fs::remove_dir_all(&folder).ok();
+    folder
+}
+
+/// Target recording the image caches used to generate textures, and the
+/// largest amount of memory they held. Only implements the required methods
+/// of [`Target`].
+struct CacheProbe {
+    target: GenericTarget<&'static str>,
+    peak: Arc<AtomicUsize>,
+    caches: Arc<std::sync::Mutex<Vec<usize>>>,
+}
+
+impl<'a> Target<GenericAsset<'a, &'static str>> for CacheProbe {
+    type Identifier = &'static str;
+
+    fn generate(
+        &self,
+        asset: &GenericAsset<'a, &'static str>,
+        images: &ImageCache,
+    ) -> Result<image::DynamicImage, ErrorKind> {
+        let img = self.target.generate(asset, images);
+        self.peak.fetch_max(images.size(), Ordering::Relaxed);
+        let mut caches = self.caches.lock().unwrap();
+        let address = images as *const ImageCache as usize;
+        if !caches.contains(&address) {
+            caches.push(address);
+        }
+        img
+    }
+
+    fn get_filename(&self, asset: &GenericAsset<'a, &'static str>) -> String {
+        self.target.get_filename(asset)
+    }
+
+    fn get_format(&self) -> image::ImageFormat {
+        Target::<GenericAsset<'a, &'static str>>::get_format(&self.target)
+    }
+}
 
 fn start_session() {
-    let resolver = GenericAssetReader::new()
-        .set_base(Regex::new(r"(.*)_.*").unwrap())
-        .add_matcher(Box::new(RegexMatcher::new(
-            "albedo",
-            Regex::new(r"(?i)albedo").unwrap(),
-        )))
-        .add_matcher(Box::new(RegexMatcher::new(
-            "ao",
-            Regex::new(r"(?i)ao").unwrap(),
-        )));
+    let resolver = reader(&["albedo", "ao"]);
 
     let albedo_ao_target = GenericTarget::new(vec![
         Some(("albedo", 0)),
@@ -26,22 +113,58 @@ fn start_session() {
 
     let session = Session::new()
         .add_target(albedo_ao_target)
-        .set_output_folder(std::path::PathBuf::from("./_tests_output_"));
+        .set_output_folder(PathBuf::from("./_tests_output_"));
 
-    let folder = std::path::PathBuf::from("./tests/textures");
+    let folder = PathBuf::from(TEXTURES);
     let assets = resolve_assets_dir(&folder, &resolver);
     assert!(
         assets.is_ok(),
         "assets folder should be processed with no error"
     );
 
-    let errors = session.run(&assets.unwrap());
+    let errors = session.run(&assets.unwrap()).errors;
     for e in &errors {
         eprintln!("Error processing file: {:?}", e);
     }
     assert_eq!(errors.len(), 0, "errors list should be empty");
 }
 
+/// Creates a folder with `count` copies of the asset `a`, runs a session on it
+/// using `threads` threads, and checks that every texture is generated.
+fn run_session_copies(count: usize, threads: Option<usize>) {
+    let name = format!("scheduler_{}_{}", count, threads.unwrap_or(0));
+    let input = fixture_folder(&name, &asset_copies(count));
+    let output = output_folder(&name);
+
+    let resolver = reader(&["albedo", "ao"]);
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                .set_name(String::from("_ra.png")),
+        )
+        .add_target(GenericTarget::new(vec![Some(("ao", 0))]).set_name(String::from("_ao.png")))
+        .set_max_threads_nb(threads)
+        .set_output_folder(output.clone());
+
+    let assets = resolve_assets_dir(&input, &resolver).unwrap();
+    assert_eq!(assets.count(), count);
+    let report = session.run(&assets);
+    assert_eq!(report.errors.len(), 0, "errors list should be empty");
+    assert_eq!(report.status, RunStatus::Completed);
+    assert_eq!(report.written.len(), count * 2);
+
+    for i in 0..count {
+        for suffix in &["_ra.png", "_ao.png"] {
+            let path = output.join(format!("{}{}", i, suffix));
+            assert!(
+                std::fs::metadata(&path).is_ok(),
+                "`{}` should be created",
+                path.display()
+            );
+        }
+    }
+}
+
 fn test_image(path: &str, expected_dimensions: (u32, u32), expected: &[Rgba<u8>]) {
     let img = open(path).unwrap();
     let img = img.as_rgba8().unwrap();
@@ -123,3 +246,750 @@ fn run_session_check_texture() {
         &[Rgba([255, 255, 255, 119]), Rgba([0, 0, 0, 185])],
     );
 }
+
+#[test]
+fn run_session_single_asset() {
+    run_session_copies(1, None);
+}
+
+#[test]
+fn run_session_two_assets() {
+    run_session_copies(2, None);
+    run_session_copies(2, Some(1));
+}
+
+#[test]
+fn run_session_many_assets() {
+    run_session_copies(17, None);
+    run_session_copies(17, Some(3));
+}
+
+#[test]
+fn run_session_cache_limit() {
+    let input = fixture_folder("cache_limit", &asset_copies(8));
+    let output = output_folder("cache_limit");
+
+    // Fits the largest image only, while 4 assets may be processed at once.
+    let max_size = ["albedo", "ao"]
+        .iter()
+        .map(|t| {
+            open(format!("./tests/textures/a_{}.png", t))
+                .unwrap()
+                .as_bytes()
+                .len()
+        })
+        .max()
+        .unwrap();
+    let peak = Arc::new(AtomicUsize::new(0));
+    let caches = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let resolver = reader(&["albedo", "ao"]);
+    let session = Session::new()
+        .add_target(CacheProbe {
+            target: GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                .set_name(String::from("_ra.png")),
+            peak: peak.clone(),
+            caches: caches.clone(),
+        })
+        .set_max_threads_nb(Some(4))
+        .set_max_cache_size(Some(max_size))
+        .set_output_folder(output);
+
+    let assets = resolve_assets_dir(&input, &resolver).unwrap();
+    let report = session.run(&assets);
+    assert_eq!(report.errors.len(), 0, "errors list should be empty");
+    assert_eq!(report.written.len(), 8);
+    // All assets share the same budget.
+    assert_eq!(caches.lock().unwrap().len(), 1);
+    assert!(peak.load(Ordering::Relaxed) > 0);
+    assert!(peak.load(Ordering::Relaxed) <= max_size);
+
+    // Without fingerprint, textures are never considered up to date.
+    assert_eq!(session.run(&assets).written.len(), 8);
+}
+
+#[test]
+fn target_fingerprint() {
+    let fingerprint =
+        |target: &GenericTarget<&str>| Target::<GenericAsset<&str>>::get_fingerprint(target);
+    let target = GenericTarget::new(vec![
+        Some(TargetInput::from(("ao", 0)).set_fallback(1.0)),
+        Some(TargetInput::Constant(0.5)),
+        None,
+        Some(TargetInput::from(("albedo", 1, vec![Transform::Invert]))),
+    ])
+    .set_name(String::from("_packed.png"));
+    assert_eq!(
+        fingerprint(&target),
+        "v1;png;u8;error;triangle;[ao:0:fallback=1,constant=0.5,none,albedo:1:invert];_packed.png"
+    );
+
+    let gamma = GenericTarget::new(vec![Some((
+        "albedo",
+        1,
+        vec![Transform::Invert, Transform::Gamma(2.2)],
+    ))])
+    .set_name(String::from("_packed.png"));
+    assert_ne!(fingerprint(&target), fingerprint(&gamma));
+    assert_ne!(
+        fingerprint(&gamma),
+        fingerprint(&GenericTarget::new(vec![Some(("albedo", 1, vec![]))]))
+    );
+}
+
+#[test]
+fn run_session_events() {
+    let resolver = reader(&["albedo"]);
+
+    let written = Arc::new(AtomicUsize::new(0));
+    let started = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let finished = Arc::new(AtomicUsize::new(0));
+    let (w, s, f) = (written.clone(), started.clone(), finished.clone());
+    let session = Session::new()
+        .add_target(GenericTarget::new(vec![Some(("albedo", 0))]).set_name(String::from("_r.png")))
+        .add_target(GenericTarget::new(vec![Some(("albedo", 1))]).set_name(String::from("_g.png")))
+        .set_force_rebuild(true)
+        .set_output_folder(output_folder("events"))
+        .set_observer(move |event: &SessionEvent| match event {
+            SessionEvent::AssetStarted { base } => {
+                s.lock().unwrap().push(base.to_string());
+            }
+            // Assets are always started before their targets.
+            SessionEvent::TargetGenerated { base, .. } => {
+                assert!(s.lock().unwrap().iter().any(|b| b == base));
+            }
+            SessionEvent::FileWritten { path, .. } => {
+                assert!(path.exists());
+                w.fetch_add(1, Ordering::SeqCst);
+            }
+            SessionEvent::RunFinished { nb_errors, .. } => {
+                assert_eq!(*nb_errors, 0);
+                f.fetch_add(1, Ordering::SeqCst);
+            }
+            _ => {}
+        });
+
+    let folder = PathBuf::from(TEXTURES);
+    let assets = resolve_assets_dir(&folder, &resolver).unwrap();
+    assert!(session.run(&assets).errors.is_empty());
+    assert_eq!(started.lock().unwrap().len(), assets.count());
+    assert_eq!(written.load(Ordering::SeqCst), assets.count() * 2);
+    assert_eq!(finished.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn run_session_cancelled() {
+    let resolver = reader(&["albedo"]);
+    let folder = PathBuf::from(TEXTURES);
+    let assets = resolve_assets_dir(&folder, &resolver).unwrap();
+
+    // Cancels the run as soon as the first texture is written.
+    let token = CancellationToken::new();
+    let observer_token = token.clone();
+    let session = Session::new()
+        .add_target(GenericTarget::new(vec![Some(("albedo", 0))]).set_name(String::from("_r.png")))
+        .set_force_rebuild(true)
+        .set_output_folder(output_folder("cancelled"))
+        .set_max_threads_nb(Some(1))
+        .set_cancellation_token(token.clone())
+        .set_observer(move |event: &SessionEvent| {
+            if let SessionEvent::FileWritten { .. } = event {
+                observer_token.cancel();
+            }
+        });
+
+    let report = session.run(&assets);
+    assert!(report.is_cancelled());
+    assert_eq!(report.written.len(), 1);
+    assert!(report.written[0].exists());
+
+    // A cancelled token stops the session before any job.
+    let report = session.run(&assets);
+    assert!(report.is_cancelled());
+    assert!(report.written.is_empty());
+}
+
+#[test]
+fn run_session_incremental() {
+    let input = fixture_folder(
+        "incremental",
+        &[("a_albedo.png", "a_albedo.png"), ("a_ao.png", "a_ao.png")],
+    );
+    let output = output_folder("incremental");
+
+    let resolver = reader(&["albedo", "ao"]);
+    let create_session = |depth: BitDepth| {
+        Session::new()
+            .add_target(
+                GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                    .set_name(String::from("_ra.png")),
+            )
+            .add_target(
+                GenericTarget::new(vec![Some(("ao", 0))])
+                    .set_name(String::from("_ao.png"))
+                    .set_bit_depth(depth),
+            )
+            .set_output_folder(output.clone())
+    };
+    let assets = resolve_assets_dir(&input, &resolver).unwrap();
+
+    let report = create_session(BitDepth::U8).run(&assets);
+    assert_eq!((report.written.len(), report.skipped.len()), (2, 0));
+    assert!(output.join(MANIFEST_FILENAME).exists());
+
+    // Nothing changed.
+    let report = create_session(BitDepth::U8).run(&assets);
+    assert_eq!((report.written.len(), report.skipped.len()), (0, 2));
+
+    // Forced rebuild.
+    let report = create_session(BitDepth::U8)
+        .set_force_rebuild(true)
+        .run(&assets);
+    assert_eq!((report.written.len(), report.skipped.len()), (2, 0));
+
+    // Target definition changed.
+    let report = create_session(BitDepth::U16).run(&assets);
+    assert_eq!(report.written, vec![output.join("a_ao.png")]);
+
+    // Input changed.
+    std::fs::write(
+        input.join("a_albedo.png"),
+        std::fs::read("./tests/textures/a_ao.png").unwrap(),
+    )
+    .unwrap();
+    let report = create_session(BitDepth::U16).run(&assets);
+    assert_eq!(report.written, vec![output.join("a_ra.png")]);
+
+    // Output removed.
+    std::fs::remove_file(output.join("a_ao.png")).unwrap();
+    let report = create_session(BitDepth::U16).run(&assets);
+    assert_eq!(report.written, vec![output.join("a_ao.png")]);
+}
+
+#[test]
+fn plan_session() {
+    let resolver = reader(&["albedo", "normal"]);
+    let output = output_folder("plan");
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![Some(("albedo", 0)), Some(("normal", 0))])
+                .set_name(String::from("_rn.png")),
+        )
+        .set_output_folder(output.clone());
+
+    let folder = PathBuf::from(TEXTURES);
+    let assets = resolve_assets_dir(&folder, &resolver).unwrap();
+    let plan = session.plan(&assets);
+    assert_eq!(plan.assets.len(), assets.count());
+    assert_eq!(plan.count_outdated(), assets.count());
+
+    let asset = plan
+        .assets
+        .iter()
+        .find(|a| a.textures.iter().any(|(_, p)| p.ends_with("b_albedo.jpg")))
+        .unwrap();
+    assert_eq!(asset.base, "b");
+    assert_eq!(
+        asset.textures,
+        vec![(String::from("albedo"), folder.join("b_albedo.jpg"))]
+    );
+    assert_eq!(asset.targets.len(), 1);
+    assert_eq!(asset.targets[0].output, output.join("b_rn.png"));
+    assert_eq!(asset.targets[0].inputs, vec![folder.join("b_albedo.jpg")]);
+    assert_eq!(asset.targets[0].missing, vec![String::from("normal")]);
+
+    // Nothing is written.
+    assert!(!output.exists());
+}
+
+#[test]
+fn run_session_errors() {
+    let input = fixture_folder(
+        "errors",
+        &[
+            ("a_albedo.png", "x_albedo.png"),
+            ("rec/a_ao.tif", "x_ao.tif"),
+        ],
+    );
+    let output = output_folder("errors");
+
+    let resolver = reader(&["albedo", "ao"]);
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                .set_name(String::from("_ra.png")),
+        )
+        .set_output_folder(output.clone());
+
+    let assets = resolve_assets_dir(&input, &resolver).unwrap();
+    let report = session.run(&assets);
+    assert_eq!(report.errors.len(), 1);
+
+    let error = &report.errors[0];
+    assert_eq!(error.asset.as_deref(), Some("x"));
+    assert_eq!(error.target.as_deref(), Some("_ra.png"));
+    assert_eq!(
+        error.inputs,
+        vec![input.join("x_albedo.png"), input.join("x_ao.tif")]
+    );
+    assert_eq!(error.output, Some(output.join("x_ra.png")));
+    assert!(matches!(
+        error.kind,
+        ErrorKind::InvalidSize {
+            expected: (2, 2),
+            actual: (1, 2)
+        }
+    ));
+}
+
+#[test]
+fn run_session_invalid_targets() {
+    let resolver = reader(&["ao"]);
+    let no_inputs: Vec<Option<(&str, u8)>> = Vec::new();
+    let session = Session::new()
+        .add_target(GenericTarget::new(vec![Some(("ao", 0))]).set_name(String::from("_ao.png")))
+        .add_target(GenericTarget::new(vec![Some(("ao", 3))]).set_name(String::from("_a.png")))
+        .add_target(GenericTarget::new(no_inputs).set_name(String::from("_empty.png")))
+        .set_force_rebuild(true)
+        .set_output_folder(output_folder("invalid"));
+
+    let folder = PathBuf::from(TEXTURES);
+    let assets = resolve_assets_dir(&folder, &resolver).unwrap();
+    let report = session.run(&assets);
+    assert_eq!(report.written.len(), assets.count());
+    assert_eq!(report.errors.len(), assets.count() * 2);
+    for error in &report.errors {
+        match error.target.as_deref() {
+            Some("_a.png") => assert!(matches!(
+                error.kind,
+                ErrorKind::InvalidChannel { channel: 3, .. }
+            )),
+            Some("_empty.png") => {
+                assert!(matches!(error.kind, ErrorKind::InvalidChannelCount(0)))
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+}
+
+#[test]
+fn resolve_conflicts() {
+    let input = fixture_folder(
+        "conflicts",
+        &[
+            ("a_ao.png", "hero_albedo.png"),
+            ("a_ao.png", "hero_ao.png"),
+            ("a_ao.png", "hero_ao_old.png"),
+        ],
+    );
+
+    let resolver = |policy: MatchPolicy| {
+        GenericAssetReader::new()
+            .set_base(Regex::new(r"([^_]*)_.*").unwrap())
+            .set_match_policy(policy)
+            .add_matcher(Box::new(RegexMatcher::new(
+                "ao",
+                Regex::new(r"(?i)_ao").unwrap(),
+            )))
+            .add_matcher(Box::new(RegexMatcher::new(
+                "occlusion",
+                Regex::new(r"(?i)_ao\.").unwrap(),
+            )))
+    };
+
+    // Only the first file matching an identifier is used.
+    let all = resolver(MatchPolicy::All);
+    let assets = resolve_assets_dir(&input, &all).unwrap();
+    let asset = &assets.get_assets()[0];
+    assert_eq!(
+        asset.get_texture_path(&"ao"),
+        Some(&input.join("hero_ao.png"))
+    );
+    assert_eq!(
+        assets.get_diagnostics(),
+        &[ResolveDiagnostic::Conflict {
+            asset: String::from("hero"),
+            id: String::from("ao"),
+            kept: input.join("hero_ao.png"),
+            discarded: input.join("hero_ao_old.png"),
+        }]
+    );
+    assert_eq!(
+        assets.get_diagnostics()[0].to_string(),
+        format!(
+            "{} and {} both matched 'ao' for asset 'hero', using {}",
+            input.join("hero_ao.png").display(),
+            input.join("hero_ao_old.png").display(),
+            input.join("hero_ao.png").display()
+        )
+    );
+
+    let first = resolver(MatchPolicy::First);
+    let assets = resolve_assets_dir(&input, &first).unwrap();
+    let asset = &assets.get_assets()[0];
+    assert_eq!(asset.get_texture_path(&"occlusion"), None);
+    assert_eq!(assets.get_diagnostics().len(), 2);
+    assert_eq!(
+        assets.get_diagnostics()[0].to_string(),
+        format!(
+            "{} matched 'ao', 'occlusion' for asset 'hero', using it for 'ao'",
+            input.join("hero_ao.png").display()
+        )
+    );
+
+    // Ambiguous files aren't used at all.
+    let exclusive = resolver(MatchPolicy::Exclusive);
+    let assets = resolve_assets_dir(&input, &exclusive).unwrap();
+    let asset = &assets.get_assets()[0];
+    assert_eq!(
+        asset.get_texture_path(&"ao"),
+        Some(&input.join("hero_ao_old.png"))
+    );
+    assert!(matches!(
+        &assets.get_diagnostics()[0],
+        ResolveDiagnostic::Ambiguous { used, .. } if used.is_empty()
+    ));
+}
+
+#[test]
+fn resolve_grouping() {
+    let input = fixture_folder(
+        "grouping",
+        &[
+            ("a_ao.png", "hero/albedo/hero_albedo.png"),
+            ("a_ao.png", "hero/masks/hero_ao.png"),
+            ("a_ao.png", "props/crate/crate_albedo.png"),
+        ],
+    );
+
+    let resolver = reader(&["albedo", "ao"]);
+    let resolve = |grouping: GroupingScope| {
+        let options = ResolveOptions {
+            grouping,
+            ..ResolveOptions::default()
+        };
+        resolve_assets_dir_with_options(&input, &resolver, &options).unwrap()
+    };
+
+    // Files of each directory are resolved separately.
+    let assets = resolve(GroupingScope::Directory);
+    let bases: Vec<&str> = assets.get_assets().iter().map(|a| a.get_base()).collect();
+    assert_eq!(bases, vec!["crate", "hero", "hero"]);
+
+    // Outputs go to the deepest folder containing all the files of an asset.
+    for grouping in &[GroupingScope::Tree, GroupingScope::Depth(1)] {
+        let assets = resolve(*grouping);
+        assert_eq!(assets.count(), 2);
+        let hero = &assets.get_assets()[1];
+        assert_eq!(hero.get_base(), "hero");
+        assert_eq!(hero.get_textures().len(), 2);
+        assert_eq!(hero.get_folder(), Some(input.join("hero").as_path()));
+        let props = &assets.get_assets()[0];
+        assert_eq!(
+            props.get_folder(),
+            Some(input.join("props/crate").as_path())
+        );
+    }
+
+    let output = output_folder("grouping");
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                .set_name(String::from("_packed.png")),
+        )
+        .set_output_folder(output.clone());
+    let plan = session.plan(&resolve(GroupingScope::Depth(1)));
+    assert_eq!(
+        plan.assets[1].targets[0].output,
+        output.join("hero").join("hero_packed.png")
+    );
+}
+
+#[test]
+fn resolve_folder_assets() {
+    let input = fixture_folder(
+        "folders",
+        &[
+            ("a_albedo.png", "bricks/brick_02/albedo.png"),
+            ("a_ao.png", "bricks/brick_02/textures/roughness.png"),
+            ("a_albedo.png", "bricks/brick_03/albedo.png"),
+        ],
+    );
+    let resolver = FolderAssetReader::new()
+        .set_root(input.clone())
+        .set_base(Regex::new(r"^[^/]+/([^/]+)/").unwrap())
+        .add_matcher(Box::new(RegexMatcher::new(
+            "albedo",
+            Regex::new(r"albedo").unwrap(),
+        )))
+        .add_matcher(Box::new(RegexMatcher::new(
+            "roughness",
+            Regex::new(r"roughness").unwrap(),
+        )));
+
+    // Files of an asset are grouped across folders, whatever the options.
+    let assets =
+        resolve_assets_dir_with_options(&input, &resolver, &ResolveOptions::default()).unwrap();
+    let bases: Vec<&str> = assets.get_assets().iter().map(|a| a.get_base()).collect();
+    assert_eq!(bases, vec!["brick_02", "brick_03"]);
+    let brick = &assets.get_assets()[0];
+    assert_eq!(
+        brick.get_textures(),
+        vec![
+            (
+                String::from("albedo"),
+                input.join("bricks/brick_02/albedo.png").as_path()
+            ),
+            (
+                String::from("roughness"),
+                input
+                    .join("bricks/brick_02/textures/roughness.png")
+                    .as_path()
+            ),
+        ]
+    );
+    assert_eq!(
+        brick.get_folder(),
+        Some(input.join("bricks/brick_02").as_path())
+    );
+}
+
+#[test]
+fn resolve_walk_options() {
+    let input = fixture_folder(
+        "walk",
+        &[
+            ("a_ao.png", "hero_albedo.png"),
+            ("a_ao.png", "hero_ao.psd"),
+            ("a_ao.png", "maps/hero_ao.png"),
+            ("a_ao.png", "maps/deep/hero_normal.png"),
+            ("a_ao.png", ".git/hero_normal.png"),
+            ("a_ao.png", "thumbs/hero_normal.png"),
+            ("a_ao.png", "out/hero_normal.png"),
+            ("a_ao.png", "old_build/hero_normal.png"),
+        ],
+    );
+    // Previous outputs are recognized by their manifest.
+    std::fs::write(input.join("old_build").join(MANIFEST_FILENAME), "").unwrap();
+    #[cfg(unix)]
+    {
+        let canonical = std::fs::canonicalize(&input).unwrap();
+        std::os::unix::fs::symlink(&canonical, input.join("loop")).unwrap();
+        // Not a loop, the folder is only walked once.
+        std::os::unix::fs::symlink(canonical.join("maps"), input.join("maps_link")).unwrap();
+    }
+
+    let resolver = reader(&["albedo", "ao", "normal"]);
+    let options = ResolveOptions {
+        grouping: GroupingScope::Tree,
+        include: vec![String::from("*_albedo.*"), String::from("maps/*")],
+        exclude: vec![String::from("thumbs")],
+        extensions: vec![String::from("PNG")],
+        max_depth: Some(1),
+        skip_hidden: true,
+        exclude_dirs: vec![input.join("out")],
+        ..ResolveOptions::default()
+    };
+    let assets = resolve_assets_dir_with_options(&input, &resolver, &options).unwrap();
+    assert_eq!(assets.count(), 1);
+    assert_eq!(
+        assets.get_assets()[0].get_textures(),
+        vec![
+            (
+                String::from("albedo"),
+                input.join("hero_albedo.png").as_path()
+            ),
+            (String::from("ao"), input.join("maps/hero_ao.png").as_path()),
+        ]
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        assets.get_diagnostics(),
+        &[ResolveDiagnostic::SymlinkLoop {
+            link: input.join("loop")
+        }]
+    );
+
+    // Links aren't walked at all.
+    let options = ResolveOptions {
+        symlinks: SymlinkPolicy::Skip,
+        ..options
+    };
+    let assets = resolve_assets_dir_with_options(&input, &resolver, &options).unwrap();
+    assert!(assets.get_diagnostics().is_empty());
+
+    // The root itself is never walked when it's excluded, or a previous output.
+    let options = ResolveOptions {
+        exclude_dirs: vec![input.clone()],
+        ..options
+    };
+    let assets = resolve_assets_dir_with_options(&input, &resolver, &options).unwrap();
+    assert_eq!(assets.count(), 0);
+    let assets = resolve_assets_dir(&input.join("old_build"), &resolver).unwrap();
+    assert_eq!(assets.count(), 0);
+
+    let options = ResolveOptions {
+        exclude: vec![String::from("[")],
+        ..ResolveOptions::default()
+    };
+    assert!(resolve_assets_dir_with_options(&input, &resolver, &options).is_err());
+}
+
+#[test]
+fn resolve_manifest_assets() {
+    let root = PathBuf::from(TEXTURES);
+    let reader = ManifestAssetReader::new(root.clone())
+        .add_asset(
+            ManifestAsset::new(String::from("wall"))
+                .add_texture(String::from("albedo"), "b_albedo.jpg".into())
+                .add_texture(String::from("ao"), "a_ao.png".into())
+                .set_folder("materials/wall".into()),
+        )
+        .add_asset(
+            ManifestAsset::new(String::from("crate"))
+                .add_texture(String::from("albedo"), "a_ao.png".into())
+                .add_texture(String::from("ao"), "crate_ao.png".into()),
+        )
+        .add_asset(
+            ManifestAsset::new(String::from("typo"))
+                .add_texture(String::from("albedo"), "tpyo_albedo.png".into())
+                .set_folder("materials/typo".into()),
+        );
+
+    // Assets are kept even when none of their files exist.
+    let assets = resolve_manifest(&reader);
+    let bases: Vec<&str> = assets.get_assets().iter().map(|a| a.get_base()).collect();
+    assert_eq!(bases, vec!["crate", "typo", "wall"]);
+    let wall = &assets.get_assets()[2];
+    assert_eq!(
+        wall.get_texture_path(&String::from("albedo")),
+        Some(&root.join("b_albedo.jpg"))
+    );
+    assert_eq!(
+        assets.get_diagnostics(),
+        &[
+            ResolveDiagnostic::NotFound {
+                asset: String::from("crate"),
+                id: String::from("ao"),
+                file: root.join("crate_ao.png"),
+            },
+            ResolveDiagnostic::NotFound {
+                asset: String::from("typo"),
+                id: String::from("albedo"),
+                file: root.join("tpyo_albedo.png"),
+            }
+        ]
+    );
+
+    let output = output_folder("manifest");
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![
+                Some(("albedo".to_string(), 0)),
+                Some(("ao".to_string(), 0)),
+            ])
+            .set_name(String::from("_packed.png")),
+        )
+        .set_output_folder(output.clone());
+    let plan = session.plan(&assets);
+    assert_eq!(
+        plan.assets[0].targets[0].output,
+        output.join("crate_packed.png")
+    );
+    assert_eq!(plan.assets[0].targets[0].missing, vec![String::from("ao")]);
+    assert_eq!(
+        plan.assets[1].targets[0].output,
+        output.join("materials/typo/typo_packed.png")
+    );
+    assert_eq!(
+        plan.assets[1].targets[0].missing,
+        vec![String::from("albedo"), String::from("ao")]
+    );
+    assert_eq!(
+        plan.assets[2].targets[0].output,
+        output.join("materials/wall/wall_packed.png")
+    );
+}
+
+#[test]
+fn run_session_missing_inputs() {
+    let input = fixture_folder(
+        "missing",
+        &[
+            ("a_albedo.png", "hero_albedo.png"),
+            ("a_ao.png", "hero_ao.png"),
+            ("a_albedo.png", "enemy_albedo.png"),
+        ],
+    );
+    let output = output_folder("missing");
+
+    let resolver = reader(&["albedo", "ao"]);
+    let assets = resolve_assets_dir(&input, &resolver).unwrap();
+
+    let required = GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+        .set_name(String::from("_required.png"));
+    let optional = GenericTarget::new(vec![
+        Some(TargetInput::from(("albedo", 0))),
+        Some(TargetInput::from(("ao", 0)).set_fallback(1.0)),
+    ])
+    .set_name(String::from("_optional.png"));
+
+    let skipped = Arc::new(AtomicUsize::new(0));
+    let counter = skipped.clone();
+    let session = Session::new()
+        .add_target(required)
+        .add_target(optional)
+        .set_output_folder(output.clone())
+        .set_observer(move |event: &SessionEvent| {
+            if let SessionEvent::TargetSkipped { base, missing, .. } = event {
+                assert_eq!(*base, "enemy");
+                assert_eq!(*missing, &[String::from("ao")]);
+                counter.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    let report = session.run(&assets);
+    assert!(report.errors.is_empty());
+    assert_eq!(skipped.load(Ordering::Relaxed), 1);
+    assert_eq!(report.written.len(), 3);
+    assert!(!output.join("enemy_required.png").exists());
+    assert_eq!(report.assets_with_missing_inputs(), vec!["enemy"]);
+    let mut missing = report.missing.clone();
+    missing.sort_by(|a, b| a.target.cmp(&b.target));
+    assert_eq!(
+        missing,
+        vec![
+            MissingInputs {
+                asset: String::from("enemy"),
+                target: String::from("_optional.png"),
+                output: output.join("enemy_optional.png"),
+                required: vec![],
+                optional: vec![String::from("ao")],
+            },
+            MissingInputs {
+                asset: String::from("enemy"),
+                target: String::from("_required.png"),
+                output: output.join("enemy_required.png"),
+                required: vec![String::from("ao")],
+                optional: vec![],
+            },
+        ]
+    );
+
+    // Optional inputs are filled with their fallback.
+    let img = open(output.join("enemy_optional.png")).unwrap();
+    assert!(img.as_luma_alpha8().unwrap().pixels().all(|p| p[1] == 255));
+
+    let session = Session::new()
+        .add_target(
+            GenericTarget::new(vec![Some(("albedo", 0)), Some(("ao", 0))])
+                .set_name(String::from("_required.png")),
+        )
+        .set_output_folder(output.clone())
+        .set_missing_input_policy(MissingInputPolicy::Fail);
+    let report = session.run(&assets);
+    assert_eq!(report.errors.len(), 1);
+    assert_eq!(report.errors[0].asset.as_deref(), Some("enemy"));
+    assert!(matches!(
+        &report.errors[0].kind,
+        ErrorKind::MissingInputs(ids) if ids == &[String::from("ao")]
+    ));
+}